@@ -0,0 +1,127 @@
+// エリア選択メニュー。
+// 世界地図のエリアを並べ、行けるエリアだけを選べる。まだ行けないエリアは名前を伏せる。
+#[allow(clippy::module_inception)]
+mod area_select_menu {
+    use crate::command_menu::{self, CommandItem, MenuFrame, MenuKey};
+    use crate::world_map::WorldMap;
+    #[cfg(feature = "gui")]
+    use iced::widget::Column;
+    #[cfg(feature = "gui")]
+    use iced::Element;
+    use std::collections::BTreeSet;
+
+    #[derive(Debug, Clone)]
+    pub enum Message {
+        OnSelectArea(String),
+        OnClickNext,
+    }
+
+    const TITLE: &str = "どこへ　いく？";
+
+    // 行けないエリアの表示
+    const LOCKED: &str = "？？？";
+
+    #[derive(Debug, Clone)]
+    struct AreaEntry {
+        name: String,
+        unlocked: bool,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct AreaSelectMenu {
+        areas: Vec<AreaEntry>,
+        // 選択しているエリア
+        pub(crate) area: Option<String>,
+    }
+
+    impl AreaSelectMenu {
+        pub fn new(world_map: &WorldMap, flags: &BTreeSet<String>) -> Self {
+            let areas = world_map
+                .areas
+                .iter()
+                .map(|area| AreaEntry {
+                    name: area.name.clone(),
+                    unlocked: area.is_unlocked(flags),
+                })
+                .collect();
+            AreaSelectMenu { areas, area: None }
+        }
+
+        pub fn update(&mut self, message: Message) {
+            match message {
+                Message::OnSelectArea(area) => {
+                    // 選択しているエリアを更新する
+                    self.area = Some(area);
+                }
+                Message::OnClickNext => {
+                    // 何もしない
+                }
+            }
+        }
+
+        // 選択しているエリアの番号
+        fn cursor(&self) -> Option<usize> {
+            let area = self.area.as_ref()?;
+            self.areas.iter().position(|entry| &entry.name == area)
+        }
+
+        // エリアを選択するコマンドメニューの項目
+        fn items(&self) -> Vec<CommandItem<Message>> {
+            self.areas
+                .iter()
+                .map(|entry| match entry.unlocked {
+                    true => CommandItem {
+                        label: entry.name.clone(),
+                        on_select: Some(Message::OnSelectArea(entry.name.clone())),
+                    },
+                    false => CommandItem {
+                        label: LOCKED.to_string(),
+                        on_select: None,
+                    },
+                })
+                .collect()
+        }
+
+        // キーボードの操作を、このメニューのメッセージに変換する
+        pub fn on_key(&self, key: MenuKey) -> Option<Message> {
+            match key {
+                MenuKey::Up | MenuKey::Down => {
+                    let selectable: Vec<bool> =
+                        self.areas.iter().map(|entry| entry.unlocked).collect();
+                    let idx = command_menu::move_cursor(self.cursor(), &selectable, key)?;
+                    Some(Message::OnSelectArea(self.areas[idx].name.clone()))
+                }
+                MenuKey::Confirm => self.area.as_ref().map(|_| Message::OnClickNext),
+                // 世界地図より前には、もどれない
+                MenuKey::Back => None,
+            }
+        }
+
+        #[cfg(feature = "gui")]
+        pub fn view(&self) -> Element<'_, Message> {
+            let mut column = Column::new();
+
+            column = column.push(TITLE);
+
+            // エリアを選択するコマンドメニューの作成
+            column = column.push(command_menu::view(self.items(), self.cursor()));
+
+            let confirm = iced::widget::button("ここへ　いく");
+            match self.area {
+                // エリアが選択されている場合だけ、次へ進める
+                Some(_) => column = column.push(confirm.on_press(Message::OnClickNext)),
+                None => column = column.push(confirm),
+            }
+
+            column.into()
+        }
+
+        // 画面に依存しないメニューの内容
+        pub fn frame(&self) -> MenuFrame {
+            command_menu::frame(TITLE, self.items(), self.cursor())
+        }
+    }
+}
+
+pub use area_select_menu::AreaSelectMenu;
+pub use area_select_menu::Message;
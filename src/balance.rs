@@ -0,0 +1,439 @@
+// 釣り合いを確かめるための、キャラクターとスキルごとの集計。
+// 敵を 1 体ずつ相手にして何度も戦闘し、表計算ソフトで扱えるように CSV や JSON に書き出す。
+#[allow(clippy::module_inception)]
+mod balance {
+    use crate::battle_event::BattleEvent;
+    use crate::battle_runner::{BattleRecord, Encounter, Outcome, Party, RunError};
+    use crate::simulation::{self, Distribution};
+    use crate::{Effect, MasterData, ParticipantId, Skill};
+    use serde::Serialize;
+    use std::collections::{BTreeMap, HashMap};
+
+    // レベルごとの集計。そのレベルの敵と 1 対 1 で戦った結果
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct LevelStats {
+        pub level: u8,
+        pub enemies: Vec<String>,
+        pub battles: usize,
+        pub win_rate: f32,
+        pub average_turns: f32,
+        pub time_to_kill: Distribution, // 敵が倒れるまでに、プレイヤーが行動した回数
+    }
+
+    // キャラクターごとの集計。ターンはそのキャラクターが行動した回数
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct CharacterStats {
+        pub name: String,
+        pub level: u8,
+        pub battles: usize,
+        pub turns: usize,
+        pub damage_per_turn: f32,
+        pub damage_taken_per_turn: f32,
+        pub status_uptime: BTreeMap<String, f32>, // 特殊状態ごとの、かかったまま行動したターンの割合
+    }
+
+    // スキルごとの集計。同じスキルでも、使った者ごとに分ける
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct SkillStats {
+        pub user: String,
+        pub skill: String,
+        pub probability: Option<u32>, // マスタデータの成功率。成功率のない効果は None
+        pub uses: usize,
+        pub hit_rate: f32, // 実際に失敗しなかった割合
+        pub damage_per_hit: f32,
+        pub expected_damage_per_turn: f32, // 成功率 × 当たったときのダメージ ÷ 1 回あたりのターン数
+        pub statuses_applied: usize,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct BalanceReport {
+        pub battles_per_enemy: usize,
+        pub levels: Vec<LevelStats>,
+        pub characters: Vec<CharacterStats>,
+        pub skills: Vec<SkillStats>,
+    }
+
+    // 敵を 1 体ずつ相手に、battles 回ずつ戦闘して集計する
+    pub fn balance_report(
+        master_data: &MasterData,
+        party: &Party,
+        battles: usize,
+        seed: u64,
+    ) -> Result<BalanceReport, RunError> {
+        let mut by_level: BTreeMap<u8, Vec<(String, Vec<BattleRecord>)>> = BTreeMap::new();
+        for enemy in &master_data.characters.characters {
+            let encounter = Encounter::Named(vec![enemy.name.clone()]);
+            let records = simulation::simulate(master_data, party, &encounter, battles, seed)?;
+            by_level
+                .entry(enemy.level.value)
+                .or_default()
+                .push((enemy.name.clone(), records));
+        }
+
+        let mut tally = Tally::default();
+        let mut levels = vec![];
+        for (level, fights) in &by_level {
+            let mut time_to_kill = vec![];
+            let records: Vec<&BattleRecord> = fights
+                .iter()
+                .flat_map(|(_, records)| records.iter())
+                .collect();
+            for record in &records {
+                time_to_kill.extend(tally.record(record));
+            }
+            let count = records.len().max(1) as f32;
+            let victories = records
+                .iter()
+                .filter(|record| record.outcome == Outcome::Victory)
+                .count();
+            levels.push(LevelStats {
+                level: *level,
+                enemies: fights.iter().map(|(name, _)| name.clone()).collect(),
+                battles: records.len(),
+                win_rate: victories as f32 / count,
+                average_turns: records.iter().map(|record| record.turns).sum::<usize>() as f32
+                    / count,
+                time_to_kill: Distribution::of(time_to_kill),
+            });
+        }
+
+        Ok(BalanceReport {
+            battles_per_enemy: battles,
+            levels,
+            characters: tally.characters(master_data),
+            skills: tally.skills(master_data),
+        })
+    }
+
+    impl BalanceReport {
+        pub fn characters_csv(&self, out: impl std::io::Write) -> csv::Result<()> {
+            let statuses: Vec<&String> = self
+                .characters
+                .iter()
+                .flat_map(|character| character.status_uptime.keys())
+                .collect::<std::collections::BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            let mut writer = csv::Writer::from_writer(out);
+            let mut header: Vec<String> = [
+                "name",
+                "level",
+                "battles",
+                "turns",
+                "damage_per_turn",
+                "damage_taken_per_turn",
+            ]
+            .iter()
+            .map(|column| column.to_string())
+            .collect();
+            header.extend(statuses.iter().map(|status| format!("uptime_{}", status)));
+            writer.write_record(&header)?;
+            for character in &self.characters {
+                let mut row = vec![
+                    character.name.clone(),
+                    character.level.to_string(),
+                    character.battles.to_string(),
+                    character.turns.to_string(),
+                    character.damage_per_turn.to_string(),
+                    character.damage_taken_per_turn.to_string(),
+                ];
+                row.extend(statuses.iter().map(|status| {
+                    let uptime = character.status_uptime.get(*status);
+                    uptime.copied().unwrap_or(0.0).to_string()
+                }));
+                writer.write_record(&row)?;
+            }
+            writer.flush()?;
+            Ok(())
+        }
+
+        pub fn skills_csv(&self, out: impl std::io::Write) -> csv::Result<()> {
+            let mut writer = csv::Writer::from_writer(out);
+            for skill in &self.skills {
+                writer.serialize(skill)?;
+            }
+            writer.flush()?;
+            Ok(())
+        }
+
+        pub fn levels_csv(&self, out: impl std::io::Write) -> csv::Result<()> {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record([
+                "level",
+                "enemies",
+                "battles",
+                "win_rate",
+                "average_turns",
+                "kills",
+                "time_to_kill_mean",
+                "time_to_kill_p50",
+                "time_to_kill_p90",
+            ])?;
+            for level in &self.levels {
+                writer.write_record([
+                    level.level.to_string(),
+                    level.enemies.join(" "),
+                    level.battles.to_string(),
+                    level.win_rate.to_string(),
+                    level.average_turns.to_string(),
+                    level.time_to_kill.count.to_string(),
+                    level.time_to_kill.mean.to_string(),
+                    level.time_to_kill.p50.to_string(),
+                    level.time_to_kill.p90.to_string(),
+                ])?;
+            }
+            writer.flush()?;
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct CharacterTally {
+        battles: usize,
+        turns: usize,
+        dealt: f32,
+        taken: f32,
+        status_turns: BTreeMap<String, usize>,
+    }
+
+    #[derive(Default)]
+    struct SkillTally {
+        uses: usize,
+        misses: usize,
+        hits: usize,
+        damage: f32,
+        statuses_applied: usize,
+    }
+
+    // すべての戦闘のできごとを数える
+    #[derive(Default)]
+    struct Tally {
+        characters: BTreeMap<String, CharacterTally>,
+        skills: BTreeMap<(String, String), SkillTally>,
+    }
+
+    impl Tally {
+        // 1 回の戦闘を数え、敵が倒れたときのプレイヤーの行動回数を返す
+        fn record(&mut self, record: &BattleRecord) -> Vec<f32> {
+            let mut statuses: HashMap<ParticipantId, Vec<String>> = HashMap::new();
+            let mut current: Option<(String, String)> = None;
+            let mut player_turns = 0;
+            let mut time_to_kill = vec![];
+            let mut appeared = std::collections::HashSet::new();
+            for event in &record.events {
+                let actor = match event {
+                    BattleEvent::SkillUsed { user, .. }
+                    | BattleEvent::Charging { user }
+                    | BattleEvent::Waiting { user } => Some(user),
+                    _ => None,
+                };
+                if let Some(actor) = actor {
+                    let character = self.characters.entry(actor.name.clone()).or_default();
+                    if appeared.insert(actor.name.clone()) {
+                        character.battles += 1;
+                    }
+                    character.turns += 1;
+                    for status in statuses.get(&actor.id).into_iter().flatten() {
+                        *character.status_turns.entry(status.clone()).or_insert(0) += 1;
+                    }
+                    if actor.id == record.player.id {
+                        player_turns += 1;
+                    }
+                }
+
+                match event {
+                    BattleEvent::SkillUsed { user, skill } => {
+                        let key = (user.name.clone(), skill.clone());
+                        self.skills.entry(key.clone()).or_default().uses += 1;
+                        current = Some(key);
+                    }
+                    BattleEvent::Miss { .. } => {
+                        if let Some(key) = &current {
+                            self.skills.entry(key.clone()).or_default().misses += 1;
+                        }
+                    }
+                    // 倒したときに余ったダメージは数えない
+                    BattleEvent::Damage { target, lost, .. } => {
+                        if let Some((user, skill)) = &current {
+                            let tally = self
+                                .skills
+                                .entry((user.clone(), skill.clone()))
+                                .or_default();
+                            tally.hits += 1;
+                            tally.damage += lost;
+                            self.characters.entry(user.clone()).or_default().dealt += lost;
+                        }
+                        self.characters
+                            .entry(target.name.clone())
+                            .or_default()
+                            .taken += lost;
+                    }
+                    BattleEvent::StatusDamage { target, amount, .. } => {
+                        self.characters
+                            .entry(target.name.clone())
+                            .or_default()
+                            .taken += amount;
+                    }
+                    BattleEvent::StatusApplied { target, status, .. } => {
+                        if let Some(key) = &current {
+                            self.skills.entry(key.clone()).or_default().statuses_applied += 1;
+                        }
+                        let active = statuses.entry(target.id).or_default();
+                        if !active.contains(&status.to_string()) {
+                            active.push(status.to_string());
+                        }
+                    }
+                    BattleEvent::StatusExpired { target, status } => {
+                        if let Some(active) = statuses.get_mut(&target.id) {
+                            active.retain(|active| *active != status.to_string());
+                        }
+                    }
+                    BattleEvent::Knockout { target } => {
+                        statuses.remove(&target.id);
+                        if *target != record.player {
+                            time_to_kill.push(player_turns as f32);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            time_to_kill
+        }
+
+        fn characters(&self, master_data: &MasterData) -> Vec<CharacterStats> {
+            self.characters
+                .iter()
+                .map(|(name, tally)| {
+                    let turns = tally.turns.max(1) as f32;
+                    let level = master_data
+                        .characters
+                        .characters
+                        .iter()
+                        .find(|character| &character.name == name)
+                        .map(|character| character.level.value)
+                        .unwrap_or(1);
+                    CharacterStats {
+                        name: name.clone(),
+                        level,
+                        battles: tally.battles,
+                        turns: tally.turns,
+                        damage_per_turn: tally.dealt / turns,
+                        damage_taken_per_turn: tally.taken / turns,
+                        status_uptime: tally
+                            .status_turns
+                            .iter()
+                            .map(|(status, count)| (status.clone(), *count as f32 / turns))
+                            .collect(),
+                    }
+                })
+                .collect()
+        }
+
+        fn skills(&self, master_data: &MasterData) -> Vec<SkillStats> {
+            self.skills
+                .iter()
+                .map(|((user, skill), tally)| {
+                    let uses = tally.uses.max(1) as f32;
+                    let hit_rate = 1.0 - tally.misses as f32 / uses;
+                    let damage_per_hit = tally.damage / tally.hits.max(1) as f32;
+                    let found = find_skill(master_data, user, skill);
+                    let probability = found.and_then(|skill| probability_of(&skill.effect));
+                    // 成功率のない効果は、実際に失敗しなかった割合で見積もる
+                    let rate = probability.map_or(hit_rate, |percentage| percentage as f32 / 100.0);
+                    let turns = found.map_or(1, turns_per_use) as f32;
+                    SkillStats {
+                        user: user.clone(),
+                        skill: skill.clone(),
+                        probability,
+                        uses: tally.uses,
+                        hit_rate,
+                        damage_per_hit,
+                        expected_damage_per_turn: rate * damage_per_hit / turns,
+                        statuses_applied: tally.statuses_applied,
+                    }
+                })
+                .collect()
+        }
+    }
+
+    // 敵のスキルは敵の覚えているもの、プレイヤーのスキルはマスタデータのものを探す
+    fn find_skill<'a>(master_data: &'a MasterData, user: &str, name: &str) -> Option<&'a Skill> {
+        let enemy = master_data
+            .characters
+            .characters
+            .iter()
+            .find(|character| character.name == user);
+        let skills = match enemy {
+            Some(enemy) => &enemy.skills.skills,
+            None => &master_data.skills.skills,
+        };
+        skills.iter().find(|skill| skill.name == name)
+    }
+
+    // 続けて使うときの、1 回あたりのターン数。ためるターンと、クールダウンで待つターンを含む
+    fn turns_per_use(skill: &Skill) -> usize {
+        1 + skill.charge_turns + skill.cooldown
+    }
+
+    // 効果の成功率
+    fn probability_of(effect: &Effect) -> Option<u32> {
+        match effect {
+            Effect::Attack(probability, _)
+            | Effect::AddSpecialStatusToEnemy(probability, _)
+            | Effect::AttackAndAddSpecialStatusToEnemy(probability, _, _, _)
+            | Effect::ChangeStatRankOfEnemy(probability, _, _, _)
+            | Effect::DispelEnemy(probability) => Some(probability.percentage),
+            _ => None,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_balance_report() {
+            let master_data = MasterData::load(&crate::default_data_path().unwrap()).unwrap();
+            let party = Party::parse("ファイアボール,アクアボール");
+            let report = balance_report(&master_data, &party, 5, 1).unwrap();
+
+            let levels: Vec<u8> = report.levels.iter().map(|level| level.level).collect();
+            assert!(levels.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!(report.levels.iter().all(|level| level.battles > 0));
+            let hero = report
+                .characters
+                .iter()
+                .find(|character| character.name == "ゆうしゃ")
+                .unwrap();
+            assert!(hero.damage_per_turn > 0.0);
+
+            // かみつくは成功率 80%
+            let bite = report
+                .skills
+                .iter()
+                .find(|skill| skill.skill == "かみつく")
+                .unwrap();
+            assert_eq!(bite.probability, Some(80));
+            assert!((bite.expected_damage_per_turn - bite.damage_per_hit * 0.8).abs() < 1e-3);
+
+            let mut csv = vec![];
+            report.skills_csv(&mut csv).unwrap();
+            let csv = String::from_utf8(csv).unwrap();
+            assert!(csv.starts_with("user,skill,probability,uses,"));
+            assert_eq!(csv.lines().count(), report.skills.len() + 1);
+        }
+
+        #[test]
+        fn test_turns_per_use() {
+            let master_data = MasterData::load(&crate::default_data_path().unwrap()).unwrap();
+
+            // ファイアブレスは 1 ターンためて、2 ターンのクールダウンがある
+            let breath = find_skill(&master_data, "ドラゴン", "ファイアブレス").unwrap();
+            assert_eq!(turns_per_use(breath), 4);
+            let fireball = find_skill(&master_data, "ゆうしゃ", "ファイアボール").unwrap();
+            assert_eq!(turns_per_use(fireball), 1);
+        }
+    }
+}
+
+pub use balance::balance_report;
@@ -0,0 +1,330 @@
+// 戦闘中に起きたできごと。
+// 戦闘処理はできごとを記録するだけで、表示用の文章は render で組み立てる。
+#[allow(clippy::module_inception)]
+mod battle_event {
+    use crate::{ParticipantId, SpecialStatusType, StatType};
+    use serde::Serialize;
+
+    // できごとに出てくる戦闘の参加者。同じ名前の敵がいても、番号で見分けられる
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct Participant {
+        pub id: ParticipantId,
+        pub name: String,  // マスタデータの名前。集計はこちらでまとめる
+        pub label: String, // 画面に出す名前。同じ敵には A, B... が付く
+    }
+
+    impl std::fmt::Display for Participant {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "{}", self.label)
+        }
+    }
+
+    // テストでは名前だけで作る。番号はプレイヤーと同じ 0 になる
+    #[cfg(test)]
+    impl From<&str> for Participant {
+        fn from(label: &str) -> Self {
+            Participant {
+                id: Default::default(),
+                name: label.to_string(),
+                label: label.to_string(),
+            }
+        }
+    }
+
+    // JSON では type に種類の名前が入る
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    #[serde(tag = "type")]
+    pub enum BattleEvent {
+        // スキルを使った
+        SkillUsed {
+            user: Participant,
+            skill: String,
+        },
+        // 攻撃が当たった
+        Hit {
+            user: Participant,
+            skill: String,
+            target: Participant,
+        },
+        // 攻撃や効果が外れた
+        Miss {
+            user: Participant,
+            skill: String,
+        },
+        // ダメージ計算に使った乱数
+        DamageRoll {
+            random: f32,
+        },
+        // ダメージを受けた。lost は実際に減った HP、hp は受けた後の残り HP
+        Damage {
+            target: Participant,
+            amount: f32,
+            lost: f32,
+            hp: f32,
+        },
+        // HP が回復した
+        Healed {
+            target: Participant,
+            amount: f32,
+        },
+        // 倒れているので回復できなかった
+        HealRefused {
+            target: Participant,
+        },
+        // MP が回復した
+        MpRestored {
+            target: Participant,
+            amount: f32,
+        },
+        // MP が足りずにスキルを使えなかった
+        NotEnoughMp {
+            user: Participant,
+        },
+        // クールダウン中でスキルを使えなかった
+        OnCooldown {
+            user: Participant,
+            skill: String,
+        },
+        // ためはじめた
+        ChargeStarted {
+            user: Participant,
+            skill: String,
+        },
+        // ため続けている
+        Charging {
+            user: Participant,
+        },
+        // ためが中断された
+        ChargeInterrupted {
+            target: Participant,
+            skill: String,
+        },
+        // 特殊状態になった。extended は既にかかっていて長引いた場合
+        StatusApplied {
+            target: Participant,
+            status: SpecialStatusType,
+            extended: bool,
+        },
+        // そうびで特殊状態を防いだ
+        StatusBlocked {
+            target: Participant,
+            status: SpecialStatusType,
+        },
+        // 特殊状態が治った
+        StatusExpired {
+            target: Participant,
+            status: SpecialStatusType,
+        },
+        // 特殊状態によるダメージ
+        StatusDamage {
+            target: Participant,
+            status: SpecialStatusType,
+            amount: f32,
+        },
+        // 能力ランクが変化した。changed は実際に変わった段階、stage は変えようとした段階
+        StatRankChanged {
+            target: Participant,
+            stat: StatType,
+            stage: i8,
+            changed: i8,
+        },
+        // 能力ランクの継続ターン数が尽きて元に戻った
+        StatRankExpired {
+            target: Participant,
+            stat: StatType,
+        },
+        // 能力ランクの変化が打ち消された。raised は上昇を消したか
+        Dispelled {
+            target: Participant,
+            raised: bool,
+        },
+        // 追加効果が無効になった
+        AdditionalEffectVoided {
+            user: Participant,
+        },
+        // HP を吸収した
+        HpDrained {
+            user: Participant,
+            amount: f32,
+        },
+        // 反動ダメージを受けた
+        Recoil {
+            user: Participant,
+            amount: f32,
+        },
+        // MP を吸収した
+        MpDrained {
+            user: Participant,
+            target: Participant,
+            amount: f32,
+        },
+        // 生き返った
+        Revived {
+            target: Participant,
+        },
+        // 倒れた
+        Knockout {
+            target: Participant,
+        },
+        // 様子を見た
+        Waiting {
+            user: Participant,
+        },
+        // 何も起こらなかった
+        NothingHappened,
+    }
+
+    // できごとを表示用の文章にする。表示しないできごとは None
+    pub fn render(event: &BattleEvent) -> Option<String> {
+        let text = match event {
+            BattleEvent::SkillUsed { user, skill } => format!("{} の {}！", user, skill),
+            // 命中したことはダメージの文章で分かる
+            BattleEvent::Hit { .. } => return None,
+            BattleEvent::Miss { user, skill } => format!("{} の {} は失敗した...", user, skill),
+            // 乱数は遊ぶ人には見せず、コマンドラインの JSON にだけ残す
+            BattleEvent::DamageRoll { .. } => return None,
+            BattleEvent::Damage {
+                target, amount, hp, ..
+            } => {
+                format!("{} に {} のダメージを与えた！ (HP: {})", target, amount, hp)
+            }
+            BattleEvent::Healed { target, amount } => {
+                format!("{} は {} の回復をした！", target, amount)
+            }
+            BattleEvent::HealRefused { target } => {
+                format!("{} は たおれていて 回復できない！", target)
+            }
+            BattleEvent::MpRestored { target, amount } => {
+                format!("{} は MP を {} 回復した！", target, amount)
+            }
+            BattleEvent::NotEnoughMp { user } => format!("{} は MP が たりない！", user),
+            BattleEvent::OnCooldown { user, skill } => {
+                format!("{} の {} は まだ つかえない！", user, skill)
+            }
+            BattleEvent::ChargeStarted { user, skill } => {
+                format!("{} は {} の ちからを ためはじめた！", user, skill)
+            }
+            BattleEvent::Charging { user } => format!("{} は ちからを ためている…", user),
+            BattleEvent::ChargeInterrupted { target, skill } => {
+                format!("{} の {} は とぎれた！", target, skill)
+            }
+            BattleEvent::StatusApplied {
+                target,
+                status,
+                extended,
+            } => render_status_applied(&target.label, status, *extended),
+            BattleEvent::StatusBlocked { target, status } => {
+                format!("{} は そうびの ちからで {} を ふせいだ！", target, status)
+            }
+            BattleEvent::StatusExpired { target, status } => {
+                format!("{} の {} が なおった。", target, status)
+            }
+            BattleEvent::StatusDamage {
+                target,
+                status,
+                amount,
+            } => format!(
+                "{} は {} で {} の ダメージを うけた！",
+                target, status, amount
+            ),
+            BattleEvent::StatRankChanged {
+                target,
+                stat,
+                stage,
+                changed,
+            } => {
+                if *changed > 0 {
+                    format!("{} の {} が {}段階 あがった！", target, stat, changed)
+                } else if *changed < 0 {
+                    format!("{} の {} が {}段階 さがった！", target, stat, -changed)
+                } else if *stage > 0 {
+                    format!("{} の {} は もう あがらない！", target, stat)
+                } else {
+                    format!("{} の {} は もう さがらない！", target, stat)
+                }
+            }
+            BattleEvent::StatRankExpired { target, stat } => {
+                format!("{} の {} が もとに もどった。", target, stat)
+            }
+            BattleEvent::Dispelled { target, raised } => {
+                if *raised {
+                    format!("{} の 能力上昇が きえた！", target)
+                } else {
+                    format!("{} の 能力低下が きえた！", target)
+                }
+            }
+            BattleEvent::AdditionalEffectVoided { user } => {
+                format!("{} の 追加効果は むこうに なった！", user)
+            }
+            BattleEvent::HpDrained { user, amount } => {
+                format!("{} は HP を {} すいとった！", user, amount)
+            }
+            BattleEvent::Recoil { user, amount } => {
+                format!("{} は はんどうで {} の ダメージを うけた！", user, amount)
+            }
+            BattleEvent::MpDrained {
+                user,
+                target,
+                amount,
+            } => format!("{} は {} の MP を {} すいとった！", user, target, amount),
+            BattleEvent::Revived { target } => format!("{} は いきかえった！", target),
+            BattleEvent::Knockout { target } => format!("{} は たおれた！", target),
+            BattleEvent::Waiting { user } => format!("{} は ようすを みている。", user),
+            BattleEvent::NothingHappened => "しかし なにも おこらなかった。".to_string(),
+        };
+        Some(text)
+    }
+
+    fn render_status_applied(target: &str, status: &SpecialStatusType, extended: bool) -> String {
+        match (status, extended) {
+            (SpecialStatusType::Poisoned, false) => format!("{} は 毒を受けた！", target),
+            (SpecialStatusType::Poisoned, true) => format!("{} の 毒が長引く！", target),
+            (SpecialStatusType::Burned, false) => format!("{} は 火傷を受けた！", target),
+            (SpecialStatusType::Burned, true) => format!("{} の 火傷が長引く！", target),
+            (SpecialStatusType::Falter, _) => format!("{} は ひるんだ！", target),
+            (SpecialStatusType::BlackOut, _) => format!("{} は 目が見えない！", target),
+            (SpecialStatusType::Frozen, _) => format!("{} は 凍りついた！", target),
+            (SpecialStatusType::Feather, false) => format!("{} は 羽を生やした！", target),
+            (SpecialStatusType::Feather, true) => format!("{} の 羽が大きくなった！", target),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_render_damage() {
+            let event = BattleEvent::Damage {
+                target: "スライム".into(),
+                amount: 3.0,
+                lost: 3.0,
+                hp: 7.0,
+            };
+            assert_eq!(
+                render(&event),
+                Some("スライム に 3 のダメージを与えた！ (HP: 7)".to_string())
+            );
+        }
+
+        #[test]
+        fn test_render_hit_is_hidden() {
+            let event = BattleEvent::Hit {
+                user: "勇者".into(),
+                skill: "ファイアボール".into(),
+                target: "スライム".into(),
+            };
+            assert_eq!(render(&event), None);
+        }
+
+        #[test]
+        fn test_render_damage_roll_is_hidden() {
+            let event = BattleEvent::DamageRoll { random: 1.1 };
+            assert_eq!(render(&event), None);
+        }
+    }
+}
+
+pub use battle_event::render;
+pub use battle_event::BattleEvent;
+pub use battle_event::Participant;
@@ -0,0 +1,173 @@
+// 戦闘ログ。
+// 遭遇してから戦闘が終わるまでのできごとをすべて残し、スクロールして見返せる。
+#[allow(clippy::module_inception)]
+mod battle_log {
+    use crate::battle_event::{render, BattleEvent};
+    #[cfg(feature = "gui")]
+    use iced::widget::{button, scrollable, text, Column};
+    #[cfg(feature = "gui")]
+    use iced::{Color, Element, Length};
+
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))] // ウィンドウのボタンからだけ送られる
+    #[derive(Debug, Clone)]
+    pub enum Message {
+        OnClickExport,
+    }
+
+    // 行の種類。フロントエンドごとに色を付ける
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum LogTone {
+        Normal,
+        Damage, // ダメージや倒れたこと
+        Heal,   // HP・MP の回復や生き返ったこと
+        Status, // 特殊状態や能力ランクの変化
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct BattleLog {
+        events: Vec<BattleEvent>,
+        pub(crate) can_export: bool, // 戦闘が終わったら、ファイルに書き出せる
+    }
+
+    impl BattleLog {
+        pub fn new() -> Self {
+            BattleLog::default()
+        }
+
+        // 新しい戦闘のために、ログを空にする
+        pub fn clear(&mut self) {
+            self.events = vec![];
+            self.can_export = false;
+        }
+
+        // 1 回の行動で起きたできごとを、ログの末尾に追加する
+        pub fn push(&mut self, events: &[BattleEvent]) {
+            self.events.extend_from_slice(events);
+        }
+
+        pub fn events(&self) -> &[BattleEvent] {
+            &self.events
+        }
+
+        // 表示用の文章の一覧
+        pub fn lines(&self) -> Vec<String> {
+            self.events.iter().filter_map(render).collect()
+        }
+
+        // 表示用の文章と、その行の種類の一覧
+        pub fn toned_lines(&self) -> Vec<(String, LogTone)> {
+            self.events
+                .iter()
+                .filter_map(|event| Some((render(event)?, tone_of(event))))
+                .collect()
+        }
+
+        // ログをテキストファイルに書き出す
+        pub fn export(&self, path: &std::path::Path) -> std::io::Result<()> {
+            let mut contents = self.lines().join("\n");
+            contents.push('\n');
+            std::fs::write(path, contents)
+        }
+
+        #[cfg(feature = "gui")]
+        pub fn view(&self) -> Element<'_, Message> {
+            let mut lines = Column::new();
+            for (line, tone) in self.toned_lines() {
+                let mut line = text(line);
+                if let Some(color) = color_of(tone) {
+                    line = line.color(color);
+                }
+                lines = lines.push(line);
+            }
+            // 新しい行が増えても、最新の行が見えるように下端に寄せる
+            let log = scrollable(lines)
+                .height(Length::Fixed(160.0))
+                .width(Length::Fill)
+                .anchor_bottom();
+
+            let mut column = Column::new().push(log);
+            if self.can_export {
+                let export = button("きろくを　かきだす").on_press(Message::OnClickExport);
+                column = column.push(export);
+            }
+            column.into()
+        }
+    }
+
+    // できごとの種類
+    pub fn tone_of(event: &BattleEvent) -> LogTone {
+        match event {
+            BattleEvent::Damage { .. }
+            | BattleEvent::StatusDamage { .. }
+            | BattleEvent::Recoil { .. }
+            | BattleEvent::Knockout { .. } => LogTone::Damage,
+            BattleEvent::Healed { .. }
+            | BattleEvent::MpRestored { .. }
+            | BattleEvent::HpDrained { .. }
+            | BattleEvent::MpDrained { .. }
+            | BattleEvent::Revived { .. } => LogTone::Heal,
+            BattleEvent::StatusApplied { .. }
+            | BattleEvent::StatusBlocked { .. }
+            | BattleEvent::StatusExpired { .. }
+            | BattleEvent::StatRankChanged { .. }
+            | BattleEvent::StatRankExpired { .. }
+            | BattleEvent::Dispelled { .. }
+            | BattleEvent::ChargeInterrupted { .. } => LogTone::Status,
+            _ => LogTone::Normal,
+        }
+    }
+
+    // ダメージは赤、回復は緑、状態の変化は紫で表示する。それ以外は既定の色
+    #[cfg(feature = "gui")]
+    fn color_of(tone: LogTone) -> Option<Color> {
+        match tone {
+            LogTone::Damage => Some(Color::from_rgb(0.9, 0.2, 0.2)),
+            LogTone::Heal => Some(Color::from_rgb(0.2, 0.7, 0.3)),
+            LogTone::Status => Some(Color::from_rgb(0.6, 0.3, 0.8)),
+            LogTone::Normal => None,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn knockout(target: &str) -> BattleEvent {
+            BattleEvent::Knockout {
+                target: target.into(),
+            }
+        }
+
+        #[test]
+        fn test_push_keeps_history() {
+            let mut log = BattleLog::new();
+            log.push(&[knockout("スライム")]);
+            log.push(&[knockout("ゴブリン")]);
+
+            assert_eq!(
+                log.lines(),
+                vec!["スライム は たおれた！", "ゴブリン は たおれた！"]
+            );
+
+            log.clear();
+            assert!(log.events().is_empty());
+        }
+
+        #[test]
+        fn test_export() {
+            let mut log = BattleLog::new();
+            log.push(&[knockout("スライム")]);
+            let path = std::env::temp_dir().join("rustern_battle_log_test.txt");
+
+            log.export(&path).unwrap();
+
+            let contents = std::fs::read_to_string(&path).unwrap();
+            assert_eq!(contents, "スライム は たおれた！\n");
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
+
+pub use battle_log::BattleLog;
+pub use battle_log::LogTone;
+pub use battle_log::Message;
@@ -1,7 +1,11 @@
 // 戦闘操作メニュー。
-// 「スキルをつかう」または「アイテムをつかう」を選択可能。
+// 「スキルをつかう」「アイテムをつかう」「ようすをみる」を選択可能。
+#[allow(clippy::module_inception)]
 mod battle_operation_menu {
-    use iced::widget::{pick_list, Column};
+    use crate::command_menu::{self, CommandItem, MenuFrame, MenuKey};
+    #[cfg(feature = "gui")]
+    use iced::widget::Column;
+    #[cfg(feature = "gui")]
     use iced::Element;
 
     #[derive(Debug, Clone, Copy)]
@@ -15,8 +19,17 @@ mod battle_operation_menu {
     pub enum Operation {
         ShowSkills,         // スキルをつかう
         ShowItemContainers, // アイテムをつかう
+        Wait,               // ようすをみる。MP がなくても選べる
     }
 
+    const OPERATIONS: [Operation; 3] = [
+        Operation::ShowSkills,
+        Operation::ShowItemContainers,
+        Operation::Wait,
+    ];
+
+    const TITLE: &str = "どうする？";
+
     impl std::fmt::Display for Operation {
         fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
             match self {
@@ -26,6 +39,9 @@ mod battle_operation_menu {
                 Operation::ShowItemContainers => {
                     write!(f, "アイテムをつかう")
                 }
+                Operation::Wait => {
+                    write!(f, "ようすをみる")
+                }
             }
         }
     }
@@ -57,18 +73,44 @@ mod battle_operation_menu {
             }
         }
 
-        pub fn view(&self) -> Element<Message> {
+        // 選択している操作の番号
+        fn cursor(&self) -> Option<usize> {
+            OPERATIONS.iter().position(|op| Some(*op) == self.operation)
+        }
+
+        // 操作を選択するコマンドメニューの項目
+        fn items(&self) -> Vec<CommandItem<Message>> {
+            OPERATIONS
+                .iter()
+                .map(|operation| CommandItem {
+                    label: operation.to_string(),
+                    on_select: Some(Message::OnSelectOperation(*operation)),
+                })
+                .collect()
+        }
+
+        // キーボードの操作を、このメニューのメッセージに変換する
+        pub fn on_key(&self, key: MenuKey) -> Option<Message> {
+            match key {
+                MenuKey::Up | MenuKey::Down => {
+                    let selectable = [true; OPERATIONS.len()];
+                    let idx = command_menu::move_cursor(self.cursor(), &selectable, key)?;
+                    Some(Message::OnSelectOperation(OPERATIONS[idx]))
+                }
+                MenuKey::Confirm => self.operation.map(|_| Message::OnClickNext),
+                // 最初のメニューなので、もどる先はない
+                MenuKey::Back => None,
+            }
+        }
+
+        #[cfg(feature = "gui")]
+        pub fn view(&self) -> Element<'_, Message> {
             let mut column = Column::new();
 
-            column = column.push("どうする？");
+            column = column.push(TITLE);
 
-            // 操作を選択するドロップダウンリストの作成
-            let operations = vec![Operation::ShowSkills, Operation::ShowItemContainers];
-            column = column.push(pick_list(
-                operations,
-                self.operation,
-                Message::OnSelectOperation,
-            ));
+            // 操作を選択するコマンドメニューの作成
+            column = column.push(command_menu::view(self.items(), self.cursor()));
 
             match self.operation {
                 Some(_operation) => {
@@ -86,8 +128,14 @@ mod battle_operation_menu {
 
             column.into()
         }
+
+        // 画面に依存しないメニューの内容
+        pub fn frame(&self) -> MenuFrame {
+            command_menu::frame(TITLE, self.items(), self.cursor())
+        }
     }
 }
 
 pub use battle_operation_menu::BattleOperationMenu;
-pub use battle_operation_menu::Message;
\ No newline at end of file
+pub use battle_operation_menu::Message;
+pub use battle_operation_menu::Operation;
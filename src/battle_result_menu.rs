@@ -1,23 +1,30 @@
+#[allow(clippy::module_inception)]
 mod battle_result_menu {
-    use crate::battle_rules::Character;
-    use crate::{battle_rules, Effect, Power, Probability, Skill, SpecialStatusType};
-    use iced::widget::{pick_list, Column};
+    use crate::battle_event::{render, BattleEvent, Participant};
+    use crate::battle_rules::{Character, Charging};
+    use crate::damage_formula::DamageFormula;
+    use crate::dice;
+    use crate::{AdditionalEffect, Effect, Power, Probability, Skill, SpecialStatusType, StatType};
+    #[cfg(feature = "gui")]
+    use iced::widget::Column;
+    #[cfg(feature = "gui")]
     use iced::Element;
     use rand::Rng;
     use std::cell::RefCell;
     use std::rc::Rc;
 
+    #[allow(dead_code)]
     #[derive(Debug, Clone)]
     pub struct BattleResultMenu {
-        skill: Skill,
         from: Rc<RefCell<Character>>,
-        to: Option<Rc<RefCell<Character>>>,
-        list_texts: Vec<String>,
+        to: Rc<RefCell<Character>>,
+        events: Vec<BattleEvent>,
         show_battle_end_button: bool,
         show_enemy_turn_button: bool,
         show_game_over_button: bool
     }
 
+    #[allow(dead_code)]
     #[derive(Debug, Clone)]
     pub enum Message {
         Initial,
@@ -26,99 +33,230 @@ mod battle_result_menu {
 
     impl BattleResultMenu {
 
-        pub(crate) fn new(skill: Skill, from: &mut Character, mut to: Option<Character>) -> Self {
+        pub(crate) fn new(
+            skill: Skill,
+            from: &mut Character,
+            to: Character,
+            damage_formula: &dyn DamageFormula,
+        ) -> Self {
             let mut menu = BattleResultMenu {
-                skill,
                 from: Rc::new(from.clone().into()),
-                to: Some(Rc::new(to.unwrap().into())),
-                list_texts: vec![],
+                to: Rc::new(to.into()),
+                events: vec![],
                 show_enemy_turn_button: false,
                 show_battle_end_button: false,
                 show_game_over_button: false
             };
+            let to_was_knocked_out = menu.to.borrow().is_knocked_out();
+            // ため中なら、選んだスキルではなくためているスキルを続ける
+            let is_charging = from.charging.is_some();
+            let skill = match &from.charging {
+                Some(charging) => charging.skill.clone(),
+                None => skill,
+            };
+            // MP が足りないか、クールダウン中ならスキルは使えない
+            if !is_charging && !from.can_use(&skill) {
+                if from.cooldown_of(&skill) > 0 {
+                    menu.events.push(BattleEvent::OnCooldown {
+                        user: from.participant(),
+                        skill: skill.name.clone(),
+                    });
+                } else {
+                    menu.events.push(BattleEvent::NotEnoughMp {
+                        user: from.participant(),
+                    });
+                }
+                return menu;
+            }
             menu.effect_before_skill();
-            match menu.skill.effect.clone() {
-                Effect::Attack(probability, power) => {
-                    // probability.percentage に基づき成功率を決める
-                    let is_hit = menu.random_hit(probability.clone());
-                    if is_hit {
-                        let damage = menu.damage(power.clone());
-                        let to_ref = menu.to.clone().unwrap();
-                        menu.list_texts.push(format!(
-                            "{} の {} が {} に {} のダメージを与えた！",
-                            from.name,
-                            menu.skill.clone().name,
-                            to_ref.borrow_mut().name,
-                            damage
-                        ));
-                        // target の残りHPを表示
-                        menu.list_texts.push(format!(
-                            "{} の HP: {}",
-                            to_ref.borrow().name,
-                            to_ref.borrow().hp
-                        ));
-                        if to_ref.borrow_mut().hp <= 0.0 {
-                            menu.list_texts.push(format!("{} は たおれた！", to_ref.borrow_mut().name));
+            if menu.prepare_skill(&skill) {
+                menu.events.push(BattleEvent::SkillUsed {
+                    user: from.participant(),
+                    skill: skill.name.clone(),
+                });
+                // 追加効果の発動に使う、主な効果の結果
+                let mut is_effective = true;
+                let mut dealt_damage = 0.0;
+                match skill.effect.clone() {
+                    Effect::Attack(probability, power) => {
+                        // probability.percentage と対象の回避率に基づき成功率を決める
+                        let is_hit = menu.random_attack_hit(probability.clone());
+                        if is_hit {
+                            dealt_damage = menu.damage(
+                                &from.participant(),
+                                &skill.name,
+                                power.clone(),
+                                damage_formula,
+                            );
+                        } else {
+                            is_effective = false;
+                            menu.push_miss(&from.participant(), &skill.name);
                         }
-                    } else {
-                        menu.list_texts.push(format!(
-                            "{} の {} は失敗した...",
-                            from.name,
-                            menu.skill.clone().name,
-                        ));
                     }
-                }
-                Effect::Heal(rate) => {
-                    // おおよその回復値：最大 HP * rate
-                    let heal_value = menu.from.borrow_mut().hp_max
-                        * rate.percentage
-                        * rand::thread_rng().gen_range((1.0)..=(1.2));
-                    menu.heal(heal_value);
-                    menu.list_texts
-                        .push(format!("{} は {} の回復をした！", from.name, heal_value));
-                }
-                Effect::AddSpecialStatusToEnemy(probability, special_status) => {
-                    if menu.random_hit(probability.clone()) {
-                        menu.apply_special_status(special_status.clone());
+                    Effect::Heal(rate) => {
+                        // おおよその回復値：最大 HP * rate
+                        let heal_value = menu.from.borrow_mut().hp_max
+                            * rate.percentage
+                            * dice::rng().gen_range(1.0..=1.2);
+                        if menu.heal(heal_value) {
+                            menu.events.push(BattleEvent::Healed {
+                                target: from.participant(),
+                                amount: heal_value,
+                            });
+                        } else {
+                            menu.events.push(BattleEvent::HealRefused {
+                                target: from.participant(),
+                            });
+                        }
                     }
-                }
-                Effect::AttackAndAddSpecialStatusToEnemy(
-                    probability,
-                    power,
-                    probability_special_status,
-                    special_status,
-                ) => {
-                    // probability.percentage に基づき成功率を決める
-                    if menu.random_hit(probability.clone()) {
-                        let damage = menu.damage(power.clone());
-                        let to_ref = menu.to.clone().unwrap();
-                        menu.list_texts.push(format!(
-                            "{} の {} が {} に {} のダメージを与えた！",
-                            from.name,
-                            menu.skill.clone().name,
-                            to_ref.borrow_mut().name,
-                            damage
-                        ));
-                        if menu.random_hit(probability_special_status.clone()) {
+                    Effect::RestoreMp(rate) => {
+                        // MP の回復値：最大 MP * rate
+                        let restore_value = menu.from.borrow().mp_max * rate.percentage;
+                        menu.from.borrow_mut().mp_increase(restore_value);
+                        menu.events.push(BattleEvent::MpRestored {
+                            target: from.participant(),
+                            amount: restore_value,
+                        });
+                    }
+                    Effect::AddSpecialStatusToEnemy(probability, special_status) => {
+                        if menu.random_hit(probability.clone()) {
                             menu.apply_special_status(special_status.clone());
                         }
                     }
+                    Effect::AttackAndAddSpecialStatusToEnemy(
+                        probability,
+                        power,
+                        probability_special_status,
+                        special_status,
+                    ) => {
+                        // probability.percentage と対象の回避率に基づき成功率を決める
+                        if menu.random_attack_hit(probability.clone()) {
+                            dealt_damage = menu.damage(
+                                &from.participant(),
+                                &skill.name,
+                                power.clone(),
+                                damage_formula,
+                            );
+                            if menu.random_hit(probability_special_status.clone()) {
+                                menu.apply_special_status(special_status.clone());
+                            }
+                        } else {
+                            is_effective = false;
+                            menu.push_miss(&from.participant(), &skill.name);
+                        }
+                    }
+                    Effect::ChangeStatRankOfSelf(stat, stage, turns) => {
+                        let changed =
+                            menu.from
+                                .borrow_mut()
+                                .add_stat_modifier(stat.clone(), stage, turns);
+                        menu.push_stat_change(&from.participant(), stat, stage, changed);
+                    }
+                    Effect::ChangeStatRankOfEnemy(probability, stat, stage, turns) => {
+                        let to_ref = menu.to.clone();
+                        if menu.random_hit(probability.clone()) {
+                            let changed =
+                                to_ref
+                                    .borrow_mut()
+                                    .add_stat_modifier(stat.clone(), stage, turns);
+                            let target = to_ref.borrow().participant();
+                            menu.push_stat_change(&target, stat, stage, changed);
+                        } else {
+                            menu.push_miss(&from.participant(), &skill.name);
+                        }
+                    }
+                    Effect::Revive(rate) => {
+                        let to_ref = menu.to.clone();
+                        let mut to = to_ref.borrow_mut();
+                        if to.revive(rate.percentage) {
+                            menu.events.push(BattleEvent::Revived {
+                                target: to.participant(),
+                            });
+                        } else {
+                            menu.events.push(BattleEvent::NothingHappened);
+                        }
+                    }
+                    Effect::DispelSelf => {
+                        if menu.from.borrow_mut().dispel_stat_modifiers(false) {
+                            menu.events.push(BattleEvent::Dispelled {
+                                target: from.participant(),
+                                raised: false,
+                            });
+                        } else {
+                            menu.events.push(BattleEvent::NothingHappened);
+                        }
+                    }
+                    Effect::DispelEnemy(probability) => {
+                        let to_ref = menu.to.clone();
+                        if menu.random_hit(probability.clone())
+                            && to_ref.borrow_mut().dispel_stat_modifiers(true)
+                        {
+                            menu.events.push(BattleEvent::Dispelled {
+                                target: to_ref.borrow().participant(),
+                                raised: true,
+                            });
+                        } else {
+                            menu.events.push(BattleEvent::NothingHappened);
+                        }
+                    }
+                }
+                if is_effective && !skill.additional_effects.is_empty() {
+                    menu.apply_additional_effects(&skill.additional_effects, dealt_damage);
+                }
+                // 再使用までのターン数を設定する
+                if skill.cooldown > 0 {
+                    menu.from
+                        .borrow_mut()
+                        .skill_cooldowns
+                        .insert(skill.name.clone(), skill.cooldown);
                 }
             }
-            // target の HP が 0 以下ならば、戦闘終了。そうでなければ戦闘続行。
-            if menu.to.as_ref().unwrap().borrow_mut().hp <= 0.0 {
-                menu.list_texts.push(format!("{} は たおれた！", menu.to.as_ref().unwrap().borrow_mut().name));
+            menu.effect_after_skill();
+            // この行動で倒れたキャラクターを、一度だけ知らせる
+            let to_ref = menu.to.clone();
+            if !to_was_knocked_out && to_ref.borrow().is_knocked_out() {
+                menu.events.push(BattleEvent::Knockout {
+                    target: to_ref.borrow().participant(),
+                });
                 menu.show_battle_end_button = true;
             }
-            menu.effect_after_skill();
-            // from の HP が 0以下ならば、戦闘終了。
-            if menu.from.borrow_mut().hp <= 0.0 {
-                menu.list_texts.push(format!("{} は たおれた！", menu.from.borrow_mut().name));
+            if menu.from.borrow().is_knocked_out() {
+                let target = menu.from.borrow().participant();
+                menu.events.push(BattleEvent::Knockout { target });
                 menu.show_game_over_button = true;
             }
             menu
         }
 
+        // 使えるスキルがなく、様子を見るだけの結果
+        pub(crate) fn waiting(from: &Character, to: Character) -> Self {
+            BattleResultMenu {
+                from: Rc::new(from.clone().into()),
+                to: Rc::new(to.into()),
+                events: vec![BattleEvent::Waiting {
+                    user: from.participant(),
+                }],
+                show_enemy_turn_button: false,
+                show_battle_end_button: false,
+                show_game_over_button: false,
+            }
+        }
+
+        // 行動した側の、戦闘後の状態
+        pub(crate) fn from(&self) -> Character {
+            self.from.borrow().clone()
+        }
+
+        // 行動の対象となった側の、戦闘後の状態
+        pub(crate) fn to(&self) -> Character {
+            self.to.borrow().clone()
+        }
+
+        // この行動で起きたできごと
+        pub(crate) fn events(&self) -> &[BattleEvent] {
+            &self.events
+        }
+
         pub(crate) fn update(&mut self, message: Message) {
             match message {
                 Message::Initial => {}
@@ -128,136 +266,653 @@ mod battle_result_menu {
             }
         }
 
-        pub(crate) fn view(&self) -> Element<Message> {
+        // 表示用の文章。1 行に 1 つのできごと
+        pub(crate) fn text(&self) -> String {
+            let lines: Vec<String> = self.events().iter().filter_map(render).collect();
+            lines.join("\n")
+        }
+
+        // visible は文字送りで表示済みの部分の文章
+        #[cfg(feature = "gui")]
+        pub(crate) fn view(&self, visible: String) -> Element<'_, Message> {
             let mut column = Column::new();
-            for text in &self.list_texts {
-                column = column.push(iced::widget::text!("{}", text));
-            }
+            column = column.push(iced::widget::text(visible));
             column = column.push(iced::widget::button("つぎへ").on_press(Message::OnClickNext));
             column.into()
         }
 
+        // ためと MP 消費を処理し、この行動でスキルが発動するかを返す
+        fn prepare_skill(&mut self, skill: &Skill) -> bool {
+            let mut from = self.from.borrow_mut();
+            if let Some(charging) = from.charging.as_mut() {
+                // MP はためはじめたときに消費済み
+                charging.turns -= 1;
+                if charging.turns > 0 {
+                    self.events.push(BattleEvent::Charging {
+                        user: from.participant(),
+                    });
+                    return false;
+                }
+                from.charging = None;
+                return true;
+            }
+            from.mp_decrease(skill.mp_cost.value);
+            if skill.charge_turns > 0 {
+                // 発動は次のターン以降。ためていることを相手に知らせる
+                self.events.push(BattleEvent::ChargeStarted {
+                    user: from.participant(),
+                    skill: skill.name.clone(),
+                });
+                from.charging = Some(Charging {
+                    skill: skill.clone(),
+                    turns: skill.charge_turns,
+                });
+                return false;
+            }
+            true
+        }
+
         fn random_hit(&mut self, probability: Probability) -> bool {
-            rand::thread_rng().gen_range(0..100) < probability.percentage
+            dice::rng().gen_range(0..100) < probability.percentage
+        }
+
+        // 攻撃の命中判定。対象の回避率のランクが高いほど当たりにくい
+        fn random_attack_hit(&mut self, probability: Probability) -> bool {
+            let evasion_rank = self.to.borrow().stat_rank(&StatType::Evasion);
+            dice::rng().gen_range(0.0..100.0) < probability.percentage as f32 / evasion_rank
+        }
+
+        // 能力ランクの変化を記録する。changed は実際に変わった段階
+        fn push_stat_change(
+            &mut self,
+            target: &Participant,
+            stat: StatType,
+            stage: i8,
+            changed: i8,
+        ) {
+            self.events.push(BattleEvent::StatRankChanged {
+                target: target.clone(),
+                stat,
+                stage,
+                changed,
+            });
+        }
+
+        fn push_miss(&mut self, user: &Participant, skill: &str) {
+            self.events.push(BattleEvent::Miss {
+                user: user.clone(),
+                skill: skill.to_string(),
+            });
         }
 
-        fn damage(&mut self, power: Power) -> f32 {
-            let random = rand::thread_rng().gen_range((1.0)..=(1.2));
-            let power_value = power.value;
+        // 対象にダメージを与え、実際に減らした HP を返す
+        fn damage(
+            &mut self,
+            user: &Participant,
+            skill: &str,
+            power: Power,
+            damage_formula: &dyn DamageFormula,
+        ) -> f32 {
+            let random = dice::rng().gen_range(1.0..=1.2);
             let from = self.from.borrow_mut();
-            let mut to = self.to.as_ref().unwrap().borrow_mut();
-            let from_attack = from.attack;
-            let to_defence = to.defence;
-            let value = random * power_value * from_attack / to_defence;
+            let mut to = self.to.borrow_mut();
+            let value = damage_formula.damage(random, &power, &from, &to);
 
-            // ダメージの計算元の値を出力
-            self.list_texts.push(format!("乱数: {}", random));
-            // self.list_texts
-            //     .push(format!("{} の攻撃力: {}", from.name, from_attack));
-            // self.list_texts
-            //     .push(format!("{} の防御力: {}", to.name, to_defence));
-            // self.list_texts
-            //     .push(format!("{} の威力: {}", self.skill.name, value));
+            // ダメージの計算元の値を記録
+            self.events.push(BattleEvent::DamageRoll { random });
+            self.events.push(BattleEvent::Hit {
+                user: user.clone(),
+                skill: skill.to_string(),
+                target: to.participant(),
+            });
 
-            // ダメージを与える
-            to.hp_decrease(value)
+            // ダメージを与える。表示するのは計算したダメージ
+            let damage = to.hp_decrease(value);
+            self.events.push(BattleEvent::Damage {
+                target: to.participant(),
+                amount: value,
+                lost: damage,
+                hp: to.hp,
+            });
+            damage
         }
 
-        fn heal(&mut self, value: f32) {
-            let mut from = self.from.borrow_mut();
-            let tmp_hp = from.hp + value;
-            if tmp_hp > from.hp_max {
-                from.hp = from.hp_max;
-            } else {
-                from.hp = tmp_hp;
-            }
+        // 自分の HP を回復する。倒れているときは回復できず false を返す
+        fn heal(&mut self, value: f32) -> bool {
+            self.from.borrow_mut().hp_increase(value)
         }
 
         fn effect_before_skill(&mut self) {
-            let mut from = self.from.borrow_mut();
-            if from.turn_of_poisoned > 0 {
-                from.turn_of_poisoned -= 1
-            };
-            if from.turn_of_burned > 0 {
-                from.turn_of_burned -= 1
-            };
-            if from.turn_of_falter > 0 {
-                from.turn_of_falter -= 1
-            };
-            if from.turn_of_blackout > 0 {
-                from.turn_of_blackout -= 1
-            };
+            let mut from_ref = self.from.borrow_mut();
+            let from = &mut *from_ref;
+            let name = from.participant();
+            // 特殊状態は、継続ターン数が尽きると治る
+            for (turns, status) in [
+                (&mut from.turn_of_poisoned, SpecialStatusType::Poisoned),
+                (&mut from.turn_of_burned, SpecialStatusType::Burned),
+                (&mut from.turn_of_falter, SpecialStatusType::Falter),
+                (&mut from.turn_of_blackout, SpecialStatusType::BlackOut),
+                (&mut from.turn_of_frost, SpecialStatusType::Frozen),
+            ] {
+                if *turns > 0 {
+                    *turns -= 1;
+                    if *turns == 0 {
+                        self.events.push(BattleEvent::StatusExpired {
+                            target: name.clone(),
+                            status,
+                        });
+                    }
+                }
+            }
+            for turns in from.skill_cooldowns.values_mut() {
+                if *turns > 0 {
+                    *turns -= 1
+                }
+            }
+            // 能力ランクの効果は、継続ターン数が尽きると元に戻る
+            for modifier in from.stat_modifiers.iter_mut() {
+                modifier.turns = modifier.turns.saturating_sub(1);
+                if modifier.turns == 0 {
+                    self.events.push(BattleEvent::StatRankExpired {
+                        target: name.clone(),
+                        stat: modifier.stat.clone(),
+                    });
+                }
+            }
+            from.stat_modifiers.retain(|modifier| modifier.turns > 0);
         }
 
         fn effect_after_skill(&mut self) {
             let mut from = self.from.borrow_mut();
             let hp_max = from.hp_max;
             if from.turn_of_poisoned > 0 {
-                from.hp_decrease(hp_max / 16.0);
+                let amount = from.hp_decrease(hp_max / 16.0);
+                self.events.push(BattleEvent::StatusDamage {
+                    target: from.participant(),
+                    status: SpecialStatusType::Poisoned,
+                    amount,
+                });
             }
             if from.turn_of_burned > 0 {
-                from.hp_decrease(hp_max / 8.0);
+                let amount = from.hp_decrease(hp_max / 8.0);
+                self.events.push(BattleEvent::StatusDamage {
+                    target: from.participant(),
+                    status: SpecialStatusType::Burned,
+                    amount,
+                });
             }
         }
-        fn apply_special_status(&mut self, special_status: SpecialStatusType) {
-            if let Some(to_ref) = self.to.as_ref() {
-                let mut to = to_ref.borrow_mut();
-                match special_status {
-                    SpecialStatusType::Poisoned => {
-                        if to.turn_of_poisoned < 1 {
-                            self.list_texts
-                                .push(format!("{} は 毒を受けた！", to.name.clone()));
-                        } else {
-                            self.list_texts
-                                .push(format!("{} の 毒が長引く！", to.name.clone()));
+        // 主な効果の後に追加効果を発動する。damage は主な効果で与えたダメージ
+        fn apply_additional_effects(&mut self, effects: &[AdditionalEffect], damage: f32) {
+            {
+                // 目が見えない、または凍っているときは追加効果が無効になる
+                let from = self.from.borrow();
+                if from.turn_of_blackout > 0 || from.turn_of_frost > 0 {
+                    self.events.push(BattleEvent::AdditionalEffectVoided {
+                        user: from.participant(),
+                    });
+                    return;
+                }
+            }
+            for effect in effects {
+                match effect {
+                    AdditionalEffect::AddSpecialStatus(special_status, probability) => {
+                        if self.random_hit(probability.clone()) {
+                            self.apply_special_status(special_status.clone());
                         }
-                        to.turn_of_poisoned += 18;
                     }
-                    SpecialStatusType::Burned => {
-                        if to.turn_of_burned < 1 {
-                            self.list_texts
-                                .push(format!("{} は 火傷を受けた！", to.name.clone()));
-                        } else {
-                            self.list_texts
-                                .push(format!("{} の 火傷が長引く！", to.name.clone()));
+                    AdditionalEffect::DrainHP(ratio) => {
+                        let value = damage * ratio.percentage;
+                        if value > 0.0 && self.heal(value) {
+                            let user = self.from.borrow().participant();
+                            self.events.push(BattleEvent::HpDrained {
+                                user,
+                                amount: value,
+                            });
                         }
-                        to.turn_of_burned += 8;
                     }
-                    SpecialStatusType::Falter => {
-                        if to.turn_of_falter < 1 && to.turn_of_frost < 0 {
-                            self.list_texts
-                                .push(format!("{} は ひるんだ！", to.name.clone()));
-                            to.turn_of_falter = 2;
+                    AdditionalEffect::Recoil(ratio) => {
+                        let value = damage * ratio.percentage;
+                        if value > 0.0 {
+                            let mut from = self.from.borrow_mut();
+                            from.hp_decrease(value);
+                            self.events.push(BattleEvent::Recoil {
+                                user: from.participant(),
+                                amount: value,
+                            });
                         }
                     }
-                    SpecialStatusType::BlackOut => {
-                        if to.turn_of_blackout < 1 {
-                            self.list_texts
-                                .push(format!("{} は 目が見えない！", to.name.clone()));
-                            to.turn_of_blackout = 2;
+                    AdditionalEffect::DrainMP(ratio) => {
+                        let mut to = self.to.borrow_mut();
+                        let value = to.mp * ratio.percentage;
+                        if value > 0.0 {
+                            to.mp_decrease(value);
+                            let mut from = self.from.borrow_mut();
+                            from.mp_increase(value);
+                            self.events.push(BattleEvent::MpDrained {
+                                user: from.participant(),
+                                target: to.participant(),
+                                amount: value,
+                            });
                         }
                     }
-                    SpecialStatusType::Frozen => {
-                        if { to.turn_of_falter < 1 && to.turn_of_frost < 0 } {
-                            self.list_texts
-                                .push(format!("{} は 凍りついた！", to.name.clone()));
-                            to.turn_of_frost = 2;
-                        }
+                }
+            }
+        }
+
+        fn apply_special_status(&mut self, special_status: SpecialStatusType) {
+            let mut to = self.to.borrow_mut();
+            // そうびで防げる特殊状態にはかからない
+            if to.equipment.is_immune(&special_status) {
+                self.events.push(BattleEvent::StatusBlocked {
+                    target: to.participant(),
+                    status: special_status,
+                });
+                return;
+            }
+            let applied = match special_status {
+                SpecialStatusType::Poisoned => {
+                    let extended = to.turn_of_poisoned > 0;
+                    to.turn_of_poisoned += 18;
+                    Some(extended)
+                }
+                SpecialStatusType::Burned => {
+                    let extended = to.turn_of_burned > 0;
+                    to.turn_of_burned += 8;
+                    Some(extended)
+                }
+                SpecialStatusType::Falter => {
+                    // ひるみでため中のスキルを中断できるよう、ひるみも凍結もなければかかる
+                    if to.turn_of_falter == 0 && to.turn_of_frost == 0 {
+                        to.turn_of_falter = 2;
+                        Some(false)
+                    } else {
+                        None
                     }
-                    SpecialStatusType::Feather => {
-                        if { to.turn_of_feather < 1 } {
-                            self.list_texts
-                                .push(format!("{} は 羽を生やした！", to.name.clone()));
-                        } else {
-                            self.list_texts
-                                .push(format!("{} の 羽が大きくなった！", to.name.clone()));
-                        }
-                        to.turn_of_feather += 5;
+                }
+                SpecialStatusType::BlackOut => {
+                    if to.turn_of_blackout < 1 {
+                        to.turn_of_blackout = 2;
+                        Some(false)
+                    } else {
+                        None
+                    }
+                }
+                SpecialStatusType::Frozen => {
+                    // ひるみと同じく、ひるみも凍結もなければかかる
+                    if to.turn_of_falter == 0 && to.turn_of_frost == 0 {
+                        to.turn_of_frost = 2;
+                        Some(false)
+                    } else {
+                        None
+                    }
+                }
+                SpecialStatusType::Feather => {
+                    let extended = to.turn_of_feather > 0;
+                    to.turn_of_feather += 5;
+                    Some(extended)
+                }
+            };
+            if let Some(extended) = applied {
+                self.events.push(BattleEvent::StatusApplied {
+                    target: to.participant(),
+                    status: special_status.clone(),
+                    extended,
+                });
+                // ひるむと、ため中のスキルは中断される
+                if special_status == SpecialStatusType::Falter {
+                    if let Some(charging) = to.charging.take() {
+                        self.events.push(BattleEvent::ChargeInterrupted {
+                            target: to.participant(),
+                            skill: charging.skill.name,
+                        });
                     }
                 }
             }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::damage_formula::DamageFormulaType;
+        use crate::equipment::{Equipment, Slot};
+        use crate::{MpCost, Rarity, Ratio};
+
+        fn skill(name: &str, effect: Effect, mp_cost: f32) -> Skill {
+            Skill {
+                name: name.into(),
+                rarity: Rarity::new(1),
+                effect,
+                mp_cost: MpCost { value: mp_cost },
+                cooldown: 0,
+                charge_turns: 0,
+                additional_effects: vec![],
+            }
+        }
+
+        fn heal_skill(mp_cost: f32) -> Skill {
+            skill("ヒール", Effect::Heal(Ratio { percentage: 0.1 }), mp_cost)
+        }
+
+        fn attack_skill() -> Skill {
+            let effect = Effect::Attack(Probability { percentage: 100 }, Power { value: 1.0 });
+            skill("たいあたり", effect, 0.0)
+        }
+
+        #[test]
+        fn test_skill_consumes_mp() {
+            let mut from = Character::for_test(1, 1.0, 1.0);
+            from.mp = 10.0;
+            from.mp_max = 10.0;
+            let to = Character::for_test(1, 1.0, 1.0);
+            let formula = DamageFormulaType::Multiplicative.formula();
+
+            let menu = BattleResultMenu::new(heal_skill(4.0), &mut from, to, formula.as_ref());
+
+            assert_eq!(menu.from().mp, 6.0);
+        }
+
+        #[test]
+        fn test_skill_without_enough_mp() {
+            let mut from = Character::for_test(1, 1.0, 1.0);
+            from.mp = 3.0;
+            from.hp = 5.0;
+            let to = Character::for_test(1, 1.0, 1.0);
+            let formula = DamageFormulaType::Multiplicative.formula();
+
+            let menu = BattleResultMenu::new(heal_skill(4.0), &mut from, to, formula.as_ref());
+
+            // MP も HP も変わらない
+            assert_eq!(menu.from().mp, 3.0);
+            assert_eq!(menu.from().hp, 5.0);
+            assert_eq!(
+                menu.events(),
+                &[BattleEvent::NotEnoughMp {
+                    user: "テスト".into()
+                }]
+            );
+        }
+
+        #[test]
+        fn test_skill_cooldown() {
+            let mut from = Character::for_test(1, 1.0, 1.0);
+            let to = Character::for_test(1, 1.0, 100.0);
+            let formula = DamageFormulaType::Multiplicative.formula();
+            let mut skill = attack_skill();
+            skill.cooldown = 1;
+
+            // 使った直後は再使用できない
+            let mut menu =
+                BattleResultMenu::new(skill.clone(), &mut from, to.clone(), formula.as_ref());
+            assert_eq!(menu.from().cooldown_of(&skill), 1);
+            from = menu.from();
+            menu = BattleResultMenu::new(skill.clone(), &mut from, to.clone(), formula.as_ref());
+            assert_eq!(
+                menu.events(),
+                &[BattleEvent::OnCooldown {
+                    user: "テスト".into(),
+                    skill: "たいあたり".into()
+                }]
+            );
+
+            // 他のスキルで 1 ターン待つと再使用できる
+            menu = BattleResultMenu::new(heal_skill(0.0), &mut from, to, formula.as_ref());
+            from = menu.from();
+            assert!(from.can_use(&skill));
+        }
+
+        #[test]
+        fn test_charge_skill() {
+            let mut from = Character::for_test(1, 1.0, 1.0);
+            let to = Character::for_test(1, 1.0, 1.0);
+            let formula = DamageFormulaType::Multiplicative.formula();
+            let mut skill = attack_skill();
+            skill.charge_turns = 1;
+
+            // 1 ターン目はためるだけ
+            let menu =
+                BattleResultMenu::new(skill.clone(), &mut from, to.clone(), formula.as_ref());
+            assert_eq!(menu.to().hp, 10.0);
+            assert!(menu.from().charging.is_some());
+
+            // 2 ターン目に発動する
+            from = menu.from();
+            let menu = BattleResultMenu::new(heal_skill(0.0), &mut from, to, formula.as_ref());
+            assert!(menu.to().hp < 10.0);
+            assert!(menu.from().charging.is_none());
+        }
+
+        #[test]
+        fn test_falter_interrupts_charge() {
+            let mut from = Character::for_test(1, 1.0, 1.0);
+            let mut to = Character::for_test(1, 1.0, 1.0);
+            to.charging = Some(Charging {
+                skill: attack_skill(),
+                turns: 1,
+            });
+            let formula = DamageFormulaType::Multiplicative.formula();
+            let effect = Effect::AddSpecialStatusToEnemy(
+                Probability { percentage: 100 },
+                SpecialStatusType::Falter,
+            );
+            let skill = skill("にらむ", effect, 0.0);
+
+            let menu = BattleResultMenu::new(skill, &mut from, to, formula.as_ref());
+
+            assert!(menu.to().charging.is_none());
+        }
+
+        #[test]
+        fn test_frozen_is_applied_and_expires() {
+            let mut from = Character::for_test(1, 1.0, 1.0);
+            let to = Character::for_test(1, 1.0, 1.0);
+            let formula = DamageFormulaType::Multiplicative.formula();
+            let effect = Effect::AddSpecialStatusToEnemy(
+                Probability { percentage: 100 },
+                SpecialStatusType::Frozen,
+            );
+            let skill = skill("こおりのいき", effect, 0.0);
+
+            let menu = BattleResultMenu::new(skill.clone(), &mut from, to, formula.as_ref());
+            let mut frozen = menu.to();
+            assert_eq!(frozen.turn_of_frost, 2);
+            assert!(menu.events().contains(&BattleEvent::StatusApplied {
+                target: "テスト".into(),
+                status: SpecialStatusType::Frozen,
+                extended: false,
+            }));
+
+            // 凍っている間は重ねてかからない
+            let menu = BattleResultMenu::new(skill, &mut from, frozen.clone(), formula.as_ref());
+            assert_eq!(menu.to().turn_of_frost, 2);
+
+            // 凍ったキャラクターが 2 回行動すると治る
+            for _ in 0..2 {
+                let menu = BattleResultMenu::new(
+                    heal_skill(0.0),
+                    &mut frozen,
+                    from.clone(),
+                    formula.as_ref(),
+                );
+                frozen = menu.from();
+            }
+            assert_eq!(frozen.turn_of_frost, 0);
+        }
+
+        #[test]
+        fn test_equipment_blocks_status() {
+            let mut from = Character::for_test(1, 1.0, 1.0);
+            let mut to = Character::for_test(1, 1.0, 1.0);
+            to.equipment.equip(Equipment {
+                name: "どくけしのおまもり".into(),
+                rarity: Rarity::new(1),
+                slot: Slot::Accessory,
+                attack: 0.0,
+                defence: 0.0,
+                immunities: vec![SpecialStatusType::Poisoned],
+                skills: vec![],
+            });
+            let formula = DamageFormulaType::Multiplicative.formula();
+            let effect = Effect::AddSpecialStatusToEnemy(
+                Probability { percentage: 100 },
+                SpecialStatusType::Poisoned,
+            );
+            let skill = skill("どくばり", effect, 0.0);
+
+            let menu = BattleResultMenu::new(skill, &mut from, to, formula.as_ref());
+
+            assert_eq!(menu.to().turn_of_poisoned, 0);
+            assert!(menu.events().iter().any(|event| matches!(
+                event,
+                BattleEvent::StatusBlocked {
+                    status: SpecialStatusType::Poisoned,
+                    ..
+                }
+            )));
+        }
+
+        #[test]
+        fn test_stat_modifier_expires() {
+            let mut from = Character::for_test(1, 1.0, 1.0);
+            let to = Character::for_test(1, 1.0, 1.0);
+            let formula = DamageFormulaType::Multiplicative.formula();
+            let effect = Effect::ChangeStatRankOfSelf(StatType::Attack, 1, 1);
+
+            let menu = BattleResultMenu::new(
+                skill("ちからため", effect, 0.0),
+                &mut from,
+                to.clone(),
+                formula.as_ref(),
+            );
+            from = menu.from();
+            assert_eq!(from.stat_stage(&StatType::Attack), 1);
+
+            // 次の行動で継続ターン数が尽きて元に戻る
+            let menu = BattleResultMenu::new(heal_skill(0.0), &mut from, to, formula.as_ref());
+            assert_eq!(menu.from().stat_stage(&StatType::Attack), 0);
+            assert!(menu.events().contains(&BattleEvent::StatRankExpired {
+                target: "テスト".into(),
+                stat: StatType::Attack
+            }));
+        }
+
+        fn drain_skill() -> Skill {
+            let mut skill = attack_skill();
+            skill.additional_effects = vec![AdditionalEffect::DrainHP(Ratio { percentage: 0.5 })];
+            skill
+        }
+
+        #[test]
+        fn test_drain_hp() {
+            let mut from = Character::for_test(1, 1.0, 1.0);
+            from.hp = 1.0;
+            let to = Character::for_test(1, 1.0, 1.0);
+            let formula = DamageFormulaType::Multiplicative.formula();
+
+            let menu = BattleResultMenu::new(drain_skill(), &mut from, to, formula.as_ref());
+
+            // 与えたダメージの半分を吸収する
+            let damage = 10.0 - menu.to().hp;
+            assert!((menu.from().hp - (1.0 + damage * 0.5)).abs() < 1e-4);
+        }
+
+        #[test]
+        fn test_drain_hp_is_capped_by_remaining_hp() {
+            let mut from = Character::for_test(1, 50.0, 1.0);
+            from.hp = 1.0;
+            let mut to = Character::for_test(1, 1.0, 1.0);
+            to.hp = 1.0;
+            let formula = DamageFormulaType::Multiplicative.formula();
+
+            let menu = BattleResultMenu::new(drain_skill(), &mut from, to, formula.as_ref());
+
+            // 残り 1 の HP に大きなダメージを与えても、吸収するのは減らした HP の半分だけ
+            assert!(menu.to().is_knocked_out());
+            assert_eq!(menu.from().hp, 1.5);
+            let lost = menu.events().iter().find_map(|event| match event {
+                BattleEvent::Damage { amount, lost, .. } if *amount > 1.0 => Some(*lost),
+                _ => None,
+            });
+            assert_eq!(lost, Some(1.0));
+        }
+
+        #[test]
+        fn test_blackout_voids_additional_effects() {
+            let mut from = Character::for_test(1, 1.0, 1.0);
+            from.hp = 1.0;
+            from.turn_of_blackout = 2;
+            let to = Character::for_test(1, 1.0, 1.0);
+            let formula = DamageFormulaType::Multiplicative.formula();
+
+            let menu = BattleResultMenu::new(drain_skill(), &mut from, to, formula.as_ref());
+
+            assert_eq!(menu.from().hp, 1.0);
+        }
+
+        #[test]
+        fn test_knockout_is_reported_once() {
+            let mut from = Character::for_test(1, 100.0, 1.0);
+            let to = Character::for_test(1, 1.0, 1.0);
+            let formula = DamageFormulaType::Multiplicative.formula();
+
+            let menu = BattleResultMenu::new(attack_skill(), &mut from, to, formula.as_ref());
+
+            assert!(menu.to().is_knocked_out());
+            let knockouts = menu
+                .events()
+                .iter()
+                .filter(|event| matches!(event, BattleEvent::Knockout { .. }))
+                .count();
+            assert_eq!(knockouts, 1);
+        }
+
+        #[test]
+        fn test_attack_events() {
+            let mut from = Character::for_test(1, 1.0, 1.0);
+            let to = Character::for_test(1, 1.0, 1.0);
+            let formula = DamageFormulaType::Multiplicative.formula();
+
+            let menu = BattleResultMenu::new(attack_skill(), &mut from, to, formula.as_ref());
+
+            let events = menu.events();
+            assert_eq!(
+                events[0],
+                BattleEvent::SkillUsed {
+                    user: "テスト".into(),
+                    skill: "たいあたり".into()
+                }
+            );
+            let hp = menu.to().hp;
+            assert!(events
+                .iter()
+                .any(|event| matches!(event, BattleEvent::Damage { hp: left, .. } if *left == hp)));
+        }
+
+        #[test]
+        fn test_revive() {
+            let mut from = Character::for_test(1, 1.0, 1.0);
+            let mut to = Character::for_test(1, 1.0, 1.0);
+            to.hp_decrease(to.hp_max);
+            let formula = DamageFormulaType::Multiplicative.formula();
+            let skill = skill("ザオラル", Effect::Revive(Ratio { percentage: 0.5 }), 0.0);
+
+            let menu = BattleResultMenu::new(skill, &mut from, to, formula.as_ref());
+
+            let to = menu.to();
+            assert!(!to.is_knocked_out());
+            assert_eq!(to.hp, 5.0);
+        }
+
+        #[test]
+        fn test_heal_refuses_knocked_out() {
+            let mut character = Character::for_test(1, 1.0, 1.0);
+            character.hp_decrease(character.hp_max);
+
+            assert!(!character.hp_increase(5.0));
+            assert_eq!(character.hp, 0.0);
+        }
+    }
 }
 
 pub use battle_result_menu::BattleResultMenu;
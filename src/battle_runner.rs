@@ -0,0 +1,458 @@
+// 画面を使わずに、戦闘を最後まで進める。
+// プレイヤーの行動はスクリプトか AI が決める。画面と同じ操作を Game に送るので、遊ぶときと同じ規則で戦う。
+#[allow(clippy::module_inception)]
+mod battle_runner {
+    use crate::battle_event::{BattleEvent, Participant};
+    use crate::battle_rules::Character;
+    use crate::game_error::GameError;
+    use crate::{battle_operation_menu, battle_result_menu, battle_ui, enemy_ai};
+    use crate::{target_enemy_menu, use_skill_menu};
+    use crate::{Characters, Game, Level, MasterData, Message, Skill, Skills};
+    use serde::Serialize;
+
+    // 決着がつかないときに打ち切るターン数
+    pub const MAX_TURNS: usize = 100;
+
+    // パーティ。いまはゆうしゃ 1 人で、覚えているスキルだけを決められる
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct Party {
+        pub skills: Option<Vec<String>>, // None ならゲームと同じくランダムに選ぶ
+    }
+
+    impl Party {
+        // カンマ区切りのスキル名
+        pub fn parse(spec: &str) -> Party {
+            let skills = spec
+                .split(',')
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .collect();
+            Party {
+                skills: Some(skills),
+            }
+        }
+    }
+
+    // 出会う敵
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Encounter {
+        Random(Level, usize), // レベルと数を決めて、ランダムに選ぶ
+        Named(Vec<String>),   // 名前で決める。同じ名前を並べてもよい
+        Area(String),         // エリアの遭遇表で決める
+    }
+
+    impl Encounter {
+        // 「レベル:数」か「@エリア名」か、カンマ区切りの敵の名前
+        pub fn parse(spec: &str) -> Result<Encounter, RunError> {
+            if let Some(area) = spec.trim().strip_prefix('@') {
+                return Ok(Encounter::Area(area.trim().to_string()));
+            }
+            if let Some((level, count)) = spec.split_once(':') {
+                let level = level.trim().parse().map_err(|_| bad_encounter(spec))?;
+                let count = count.trim().parse().map_err(|_| bad_encounter(spec))?;
+                return Ok(Encounter::Random(Level::new(level), count));
+            }
+            let names: Vec<String> = spec
+                .split(',')
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .collect();
+            if names.is_empty() {
+                return Err(bad_encounter(spec));
+            }
+            Ok(Encounter::Named(names))
+        }
+
+        fn pick(&self, master_data: &MasterData) -> Result<Vec<Character>, RunError> {
+            let enemies = match self {
+                Encounter::Random(level, count) => {
+                    master_data.characters.random_pick(level.clone(), *count)
+                }
+                Encounter::Named(names) => names
+                    .iter()
+                    .map(|name| {
+                        master_data
+                            .characters
+                            .characters
+                            .iter()
+                            .find(|enemy| &enemy.name == name)
+                            .cloned()
+                            .ok_or_else(|| RunError::UnknownEnemy(name.clone()))
+                    })
+                    .collect::<Result<_, _>>()?,
+                Encounter::Area(area) => master_data
+                    .encounter_tables
+                    .roll(area, &master_data.characters)?,
+            };
+            if enemies.is_empty() {
+                return Err(RunError::NoEnemies);
+            }
+            Ok(enemies)
+        }
+    }
+
+    fn bad_encounter(spec: &str) -> RunError {
+        RunError::BadEncounter(spec.to_string())
+    }
+
+    // プレイヤーの行動の決め方
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Policy {
+        Script(Vec<ScriptAction>), // 書かれた順に行動する
+        Ai,                        // 使えるスキルからランダムに選び、生き残っている先頭の敵を狙う
+    }
+
+    // スクリプトの 1 行。スキル名と、狙う敵の番号
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ScriptAction {
+        pub skill: String,
+        pub target: usize,
+    }
+
+    // 1 行に「スキル名 敵の番号」を書く。番号を省くと先頭の敵を狙う。# から後ろと空行は読み飛ばす
+    pub fn parse_script(text: &str) -> Result<Vec<ScriptAction>, RunError> {
+        let mut actions = vec![];
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let bad_script = || RunError::BadScript {
+                line: idx + 1,
+                text: line.to_string(),
+            };
+            let mut words = line.split_whitespace();
+            let skill = words.next().ok_or_else(bad_script)?.to_string();
+            let target = match words.next() {
+                Some(target) => target.parse().map_err(|_| bad_script())?,
+                None => 0,
+            };
+            if words.next().is_some() {
+                return Err(bad_script());
+            }
+            actions.push(ScriptAction { skill, target });
+        }
+        Ok(actions)
+    }
+
+    // 戦闘の結末
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Outcome {
+        Victory,
+        Defeat,
+        Stalled,   // 使えるスキルがなく、プレイヤーが行動できない
+        TurnLimit, // 決着がつかないまま、ターン数の上限に達した
+    }
+
+    impl std::fmt::Display for Outcome {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                Outcome::Victory => write!(f, "しょうり"),
+                Outcome::Defeat => write!(f, "はいぼく"),
+                Outcome::Stalled => write!(f, "こうどう　できない"),
+                Outcome::TurnLimit => write!(f, "じかんぎれ"),
+            }
+        }
+    }
+
+    // 1 回の戦闘の記録
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct BattleRecord {
+        pub outcome: Outcome,
+        pub turns: usize,        // プレイヤーが行動を選んだ回数
+        pub player: Participant, // できごとの中で、プレイヤーを指す参加者
+        pub events: Vec<BattleEvent>,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum RunError {
+        Load(String), // マスタデータやスクリプトを読み込めない
+        Io(String),   // 結果を書き出せない
+        UnknownSkill(String),
+        UnknownEnemy(String),
+        NoEnemies,
+        NoSuchTarget(usize),
+        BadEncounter(String),
+        BadTarget(String),
+        BadScript { line: usize, text: String },
+        ScriptEnded { turn: usize }, // 決着の前にスクリプトが終わった
+        Game(GameError),
+    }
+
+    impl From<GameError> for RunError {
+        fn from(error: GameError) -> Self {
+            RunError::Game(error)
+        }
+    }
+
+    impl From<std::io::Error> for RunError {
+        fn from(error: std::io::Error) -> Self {
+            RunError::Io(error.to_string())
+        }
+    }
+
+    impl std::fmt::Display for RunError {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                RunError::Load(error) => write!(f, "よみこめない：{}", error),
+                RunError::Io(error) => write!(f, "かきだせない：{}", error),
+                RunError::UnknownSkill(name) => write!(f, "{} という　スキルは　ない", name),
+                RunError::UnknownEnemy(name) => write!(f, "{} という　てきは　いない", name),
+                RunError::NoEnemies => write!(f, "であう　てきが　いない"),
+                RunError::NoSuchTarget(idx) => write!(f, "{} ばんめの　てきは　いない", idx),
+                RunError::BadEncounter(spec) => {
+                    write!(
+                        f,
+                        "てきの　してい {} は　「レベル:かず」か　なまえの　ならび",
+                        spec
+                    )
+                }
+                RunError::BadTarget(spec) => {
+                    write!(
+                        f,
+                        "もくひょうの　してい {} は　「レベル:しょうりりつ:ターンすう」",
+                        spec
+                    )
+                }
+                RunError::BadScript { line, text } => {
+                    write!(
+                        f,
+                        "スクリプトの　{} ぎょうめ　「{}」が　よめない",
+                        line, text
+                    )
+                }
+                RunError::ScriptEnded { turn } => {
+                    write!(f, "{} ターンめの　こうどうが　スクリプトに　ない", turn)
+                }
+                RunError::Game(error) => write!(f, "{}", error),
+            }
+        }
+    }
+
+    // 戦闘を最後まで進める。乱数は dice::seed で決めておく
+    pub fn run_battle(
+        master_data: &MasterData,
+        party: &Party,
+        encounter: &Encounter,
+        policy: &Policy,
+    ) -> Result<BattleRecord, RunError> {
+        let mut game = Game::with_master_data(master_data.clone());
+        if let Some(names) = &party.skills {
+            let skills = names
+                .iter()
+                .map(|name| find_skill(&master_data.skills, name))
+                .collect::<Result<_, _>>()?;
+            game.usable_skills = Skills { skills };
+            game.player.skills = game.usable_skills.clone();
+        }
+        game.encountered_enemies = Some(Characters::for_battle(encounter.pick(master_data)?));
+        game.update(Message::BattleOperationMenu(
+            battle_operation_menu::Message::Initial,
+        ));
+
+        let mut script = match policy {
+            Policy::Script(actions) => Some(actions.iter()),
+            Policy::Ai => None,
+        };
+        let mut turns = 0;
+        loop {
+            if let Some(error) = game.error.take() {
+                return Err(error.into());
+            }
+            match game.battle_ui.current() {
+                Some(battle_ui::Screen::Root(_)) => {
+                    if turns == MAX_TURNS {
+                        return Ok(record(&game, Outcome::TurnLimit, turns));
+                    }
+                    turns += 1;
+                    let action = match &mut script {
+                        Some(actions) => {
+                            let action = actions
+                                .next()
+                                .ok_or(RunError::ScriptEnded { turn: turns })?;
+                            let skill = find_skill(&game.usable_skills, &action.skill)?;
+                            Some((skill, action.target))
+                        }
+                        None => ai_action(&game),
+                    };
+                    let Some((skill, target)) = action else {
+                        return Ok(record(&game, Outcome::Stalled, turns));
+                    };
+                    choose(&mut game, skill, target)?;
+                }
+                Some(battle_ui::Screen::Result(_)) | Some(battle_ui::Screen::EnemyTurn(_)) => {
+                    game.update(Message::BattleResultMenu(
+                        battle_result_menu::Message::OnClickNext,
+                    ));
+                }
+                // 行動を選んだ後は、かならず結果の画面になる
+                Some(_) => return Ok(record(&game, Outcome::Stalled, turns)),
+                None => break,
+            }
+        }
+        let outcome = if game.player.is_knocked_out() {
+            Outcome::Defeat
+        } else {
+            Outcome::Victory
+        };
+        Ok(record(&game, outcome, turns))
+    }
+
+    fn find_skill(skills: &Skills, name: &str) -> Result<Skill, RunError> {
+        skills
+            .skills
+            .iter()
+            .find(|skill| skill.name == name)
+            .cloned()
+            .ok_or_else(|| RunError::UnknownSkill(name.to_string()))
+    }
+
+    // 使えるスキルからランダムに選び、生き残っている先頭の敵を狙う
+    fn ai_action(game: &Game) -> Option<(Skill, usize)> {
+        let skill = enemy_ai::choose_skill(&game.player)?;
+        let target = game
+            .encountered_enemies
+            .as_ref()?
+            .characters
+            .iter()
+            .position(|enemy| !enemy.is_knocked_out())?;
+        Some((skill, target))
+    }
+
+    // 戦闘操作メニューから、スキルと敵を選ぶ
+    fn choose(game: &mut Game, skill: Skill, target: usize) -> Result<(), RunError> {
+        let enemy = game
+            .encountered_enemies
+            .as_ref()
+            .and_then(|enemies| enemies.characters.get(target))
+            .map(|enemy| enemy.id)
+            .ok_or(RunError::NoSuchTarget(target))?;
+        let messages = [
+            Message::BattleOperationMenu(battle_operation_menu::Message::OnSelectOperation(
+                battle_operation_menu::Operation::ShowSkills,
+            )),
+            Message::BattleOperationMenu(battle_operation_menu::Message::OnClickNext),
+            Message::UseSkillMenu(use_skill_menu::Message::OnSelectSkill(skill)),
+            Message::UseSkillMenu(use_skill_menu::Message::OnClickNext),
+            Message::TargetEnemyMenu(target_enemy_menu::Message::OnSelectEnemy(enemy)),
+            Message::TargetEnemyMenu(target_enemy_menu::Message::OnClickNext),
+        ];
+        for message in messages {
+            game.update(message);
+            if let Some(error) = game.error.take() {
+                return Err(error.into());
+            }
+        }
+        Ok(())
+    }
+
+    fn record(game: &Game, outcome: Outcome, turns: usize) -> BattleRecord {
+        BattleRecord {
+            outcome,
+            turns,
+            player: game.player.participant(),
+            events: game.battle_log.events().to_vec(),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::dice;
+
+        fn master_data() -> MasterData {
+            MasterData::load(&crate::default_data_path().unwrap()).unwrap()
+        }
+
+        #[test]
+        fn test_parse_specs() {
+            assert_eq!(
+                Encounter::parse("2:3"),
+                Ok(Encounter::Random(Level::new(2), 3))
+            );
+            assert_eq!(
+                Encounter::parse("スライム, スライム"),
+                Ok(Encounter::Named(vec!["スライム".into(), "スライム".into()]))
+            );
+            assert!(Encounter::parse("2:たくさん").is_err());
+            assert_eq!(
+                Encounter::parse("@はじまりのそうげん"),
+                Ok(Encounter::Area("はじまりのそうげん".into()))
+            );
+
+            let script =
+                parse_script("# ひとりめ\nファイアボール 1\n\nヒール  # かいふく\n").unwrap();
+            assert_eq!(
+                script,
+                vec![
+                    ScriptAction {
+                        skill: "ファイアボール".into(),
+                        target: 1
+                    },
+                    ScriptAction {
+                        skill: "ヒール".into(),
+                        target: 0
+                    },
+                ]
+            );
+            assert_eq!(
+                parse_script("ヒール 0 1"),
+                Err(RunError::BadScript {
+                    line: 1,
+                    text: "ヒール 0 1".into()
+                })
+            );
+        }
+
+        #[test]
+        fn test_same_seed_same_battle() {
+            let master_data = master_data();
+            let party = Party::parse("ファイアボール,アクアボール");
+            let encounter = Encounter::parse("ゴブリン,スライム").unwrap();
+            let run = || {
+                dice::seed(7);
+                run_battle(&master_data, &party, &encounter, &Policy::Ai).unwrap()
+            };
+            let record = run();
+            assert_eq!(record, run());
+            assert!(record.turns > 0);
+            assert!(!record.events.is_empty());
+        }
+
+        #[test]
+        fn test_script_errors() {
+            let master_data = master_data();
+            let party = Party::parse("ヒール");
+            let encounter = Encounter::parse("スライム").unwrap();
+
+            let script = Policy::Script(vec![ScriptAction {
+                skill: "ファイアボール".into(),
+                target: 0,
+            }]);
+            assert_eq!(
+                run_battle(&master_data, &party, &encounter, &script),
+                Err(RunError::UnknownSkill("ファイアボール".into()))
+            );
+
+            // スライムは HP が多いので、ヒール 1 回では決着がつかない
+            let script = Policy::Script(vec![ScriptAction {
+                skill: "ヒール".into(),
+                target: 0,
+            }]);
+            assert_eq!(
+                run_battle(&master_data, &party, &encounter, &script),
+                Err(RunError::ScriptEnded { turn: 2 })
+            );
+        }
+    }
+}
+
+pub use battle_runner::parse_script;
+pub use battle_runner::run_battle;
+pub use battle_runner::BattleRecord;
+pub use battle_runner::Encounter;
+pub use battle_runner::Outcome;
+pub use battle_runner::Party;
+pub use battle_runner::Policy;
+pub use battle_runner::RunError;
@@ -0,0 +1,240 @@
+// 戦闘画面のメニューの状態遷移。
+// Root → Skills/Items → Target → Result → EnemyTurn の順に進む。
+// 選択中の画面は積み重ねておき、「もどる」で 1 つ前の画面に戻れる。
+#[allow(clippy::module_inception)]
+mod battle_ui {
+    use crate::battle_operation_menu::BattleOperationMenu;
+    use crate::battle_result_menu::BattleResultMenu;
+    use crate::target_enemy_menu::TargetEnemyMenu;
+    use crate::use_item_menu::UseItemMenu;
+    use crate::use_skill_menu::UseSkillMenu;
+    use crate::{Item, Skill};
+
+    // 敵に向けて使うもの
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Action {
+        Skill(Skill),
+        Item(Item),
+    }
+
+    // 戦闘画面に表示するメニュー
+    pub enum Screen {
+        Root(BattleOperationMenu),       // どうする？
+        Skills(UseSkillMenu),            // スキルの選択
+        Items(UseItemMenu),              // アイテムの選択
+        Target(TargetEnemyMenu, Action), // 狙う敵の選択
+        Result(BattleResultMenu),        // プレイヤーの行動の結果
+        EnemyTurn(BattleResultMenu),     // 敵の行動の結果
+    }
+
+    // 画面の種類。遷移できるかの判定に使う
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum ScreenKind {
+        Root,
+        Skills,
+        Items,
+        Target,
+        Result,
+        EnemyTurn,
+    }
+
+    impl Screen {
+        pub fn kind(&self) -> ScreenKind {
+            match self {
+                Screen::Root(_) => ScreenKind::Root,
+                Screen::Skills(_) => ScreenKind::Skills,
+                Screen::Items(_) => ScreenKind::Items,
+                Screen::Target(_, _) => ScreenKind::Target,
+                Screen::Result(_) => ScreenKind::Result,
+                Screen::EnemyTurn(_) => ScreenKind::EnemyTurn,
+            }
+        }
+    }
+
+    // 許されていない画面の遷移
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct InvalidTransition {
+        pub from: Option<ScreenKind>,
+        pub to: ScreenKind,
+    }
+
+    impl std::fmt::Display for InvalidTransition {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self.from {
+                Some(from) => write!(f, "{:?} から {:?} には すすめない", from, self.to),
+                None => write!(f, "{:?} から はじめることは できない", self.to),
+            }
+        }
+    }
+
+    #[derive(Default)]
+    pub struct BattleUi {
+        stack: Vec<Screen>, // 末尾が表示中の画面
+    }
+
+    impl BattleUi {
+        pub fn current(&self) -> Option<&Screen> {
+            self.stack.last()
+        }
+
+        pub fn current_mut(&mut self) -> Option<&mut Screen> {
+            self.stack.last_mut()
+        }
+
+        pub fn kind(&self) -> Option<ScreenKind> {
+            self.current().map(Screen::kind)
+        }
+
+        // 表示中の行動の結果。プレイヤーと敵のどちらの行動でもよい
+        pub fn result_menu(&self) -> Option<&BattleResultMenu> {
+            match self.current() {
+                Some(Screen::Result(menu)) | Some(Screen::EnemyTurn(menu)) => Some(menu),
+                _ => None,
+            }
+        }
+
+        // 次の画面へ進む。選択中の画面は積み重ね、行動の結果や新しいターンからは戻れない
+        pub fn go(&mut self, screen: Screen) -> Result<(), InvalidTransition> {
+            let from = self.kind();
+            let to = screen.kind();
+            if !can_go(from, to) {
+                return Err(InvalidTransition { from, to });
+            }
+            match to {
+                ScreenKind::Skills | ScreenKind::Items | ScreenKind::Target => {
+                    self.stack.push(screen)
+                }
+                ScreenKind::Root | ScreenKind::Result | ScreenKind::EnemyTurn => {
+                    self.stack = vec![screen]
+                }
+            }
+            Ok(())
+        }
+
+        // 1 つ前の画面に戻る。戻れない画面なら false を返す
+        pub fn back(&mut self) -> bool {
+            match self.kind() {
+                Some(ScreenKind::Skills | ScreenKind::Items | ScreenKind::Target) => {
+                    self.stack.pop();
+                    true
+                }
+                _ => false,
+            }
+        }
+
+        // 戦闘が終わったので、メニューを閉じる
+        pub fn close(&mut self) {
+            self.stack = vec![];
+        }
+    }
+
+    // from の画面から to の画面へ進めるか
+    fn can_go(from: Option<ScreenKind>, to: ScreenKind) -> bool {
+        use ScreenKind::*;
+        matches!(
+            (from, to),
+            (None, Root)
+                | (Some(Root), Skills | Items)
+                // 様子を見るときは、敵を選ばずに行動の結果へ進む
+                | (Some(Root), Result)
+                | (Some(Skills | Items), Target)
+                | (Some(Target), Result)
+                // 行動の結果の後は、敵の行動・ため中のスキルの発動・次のターンのいずれか
+                | (Some(Result | EnemyTurn), Root | Result | EnemyTurn)
+        )
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::battle_rules::Character;
+        use crate::{Characters, Skills};
+
+        fn skills_screen() -> Screen {
+            let user = Character::for_test(1, 1.0, 1.0);
+            Screen::Skills(UseSkillMenu::new(Skills { skills: vec![] }, user))
+        }
+
+        fn target_screen() -> Screen {
+            let enemies = Characters {
+                characters: vec![Character::for_test(1, 1.0, 1.0)],
+            };
+            let skill = Skill {
+                name: "たいあたり".into(),
+                rarity: crate::Rarity::new(1),
+                effect: crate::Effect::DispelSelf,
+                mp_cost: Default::default(),
+                cooldown: 0,
+                charge_turns: 0,
+                additional_effects: vec![],
+            };
+            Screen::Target(TargetEnemyMenu::new(enemies), Action::Skill(skill))
+        }
+
+        fn result_screen() -> Screen {
+            let character = Character::for_test(1, 1.0, 1.0);
+            Screen::Result(BattleResultMenu::waiting(&character, character.clone()))
+        }
+
+        fn ui_at_target() -> BattleUi {
+            let mut ui = BattleUi::default();
+            ui.go(Screen::Root(BattleOperationMenu::new())).unwrap();
+            ui.go(skills_screen()).unwrap();
+            ui.go(target_screen()).unwrap();
+            ui
+        }
+
+        #[test]
+        fn test_valid_transitions() {
+            let mut ui = ui_at_target();
+            assert_eq!(ui.kind(), Some(ScreenKind::Target));
+            ui.go(result_screen()).unwrap();
+            assert_eq!(ui.kind(), Some(ScreenKind::Result));
+            ui.go(Screen::Root(BattleOperationMenu::new())).unwrap();
+            assert_eq!(ui.kind(), Some(ScreenKind::Root));
+        }
+
+        #[test]
+        fn test_invalid_transitions_are_rejected() {
+            let mut ui = BattleUi::default();
+            // 最初の画面は Root だけ
+            assert!(ui.go(skills_screen()).is_err());
+            assert!(ui.go(result_screen()).is_err());
+
+            ui.go(Screen::Root(BattleOperationMenu::new())).unwrap();
+            // スキルやアイテムを選ばずに、敵の選択へは進めない
+            assert_eq!(
+                ui.go(target_screen()),
+                Err(InvalidTransition {
+                    from: Some(ScreenKind::Root),
+                    to: ScreenKind::Target
+                })
+            );
+            assert_eq!(ui.kind(), Some(ScreenKind::Root));
+
+            // 行動の結果から、スキルの選択には戻れない
+            let mut ui = ui_at_target();
+            ui.go(result_screen()).unwrap();
+            assert!(ui.go(skills_screen()).is_err());
+            assert!(!ui.back());
+            assert_eq!(ui.kind(), Some(ScreenKind::Result));
+        }
+
+        #[test]
+        fn test_back_stack() {
+            let mut ui = ui_at_target();
+            assert!(ui.back());
+            assert_eq!(ui.kind(), Some(ScreenKind::Skills));
+            assert!(ui.back());
+            assert_eq!(ui.kind(), Some(ScreenKind::Root));
+            // Root からは戻れない
+            assert!(!ui.back());
+            assert_eq!(ui.kind(), Some(ScreenKind::Root));
+        }
+    }
+}
+
+pub use battle_ui::Action;
+pub use battle_ui::BattleUi;
+pub use battle_ui::InvalidTransition;
+pub use battle_ui::Screen;
@@ -0,0 +1,281 @@
+// ターミナルで遊ぶためのフロントエンド。
+// SSH 越しなど iced のウィンドウを開けない環境でも、同じシナリオと戦闘を遊べる。
+// iced なしでビルドするには cargo run --bin rustern-battle-tui --no-default-features。
+// 矢印キーで選び、Enter で決定、Esc でもどる。スペースで文字送りを飛ばし、q で終わる。
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, Paragraph, Wrap};
+use rustern_battle::{BadgeKind, CardFrame, Frame, Game, LogTone, MenuFrame, MenuKey};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+// キー入力を待つ間隔。文字送りなどの演出はこの間隔で進む
+const POLL_INTERVAL: Duration = Duration::from_millis(33);
+
+fn main() -> ExitCode {
+    // マスタデータを読み込めなければ、画面を切り替える前に知らせる
+    let game = match Game::new() {
+        Ok(game) => game.with_autosave(),
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, game);
+    ratatui::restore();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(terminal: &mut ratatui::DefaultTerminal, mut game: Game) -> std::io::Result<()> {
+    loop {
+        if game.is_animating() {
+            game.advance(Instant::now());
+        }
+        let frame = game.frame();
+        terminal.draw(|terminal_frame| draw(terminal_frame, &frame))?;
+
+        if !event::poll(POLL_INTERVAL)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') => return Ok(()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Char(' ') => game.skip_text(),
+            KeyCode::Up => game.press(MenuKey::Up),
+            KeyCode::Down => game.press(MenuKey::Down),
+            KeyCode::Enter => game.press(MenuKey::Confirm),
+            KeyCode::Esc => game.press(MenuKey::Back),
+            _ => {}
+        }
+    }
+}
+
+// 上から順に、エラー・情報・敵・プレイヤー・戦闘ログ・メニュー・操作の説明を並べる
+fn draw(terminal_frame: &mut ratatui::Frame, frame: &Frame) {
+    let error_height = if frame.error.is_some() { 3 } else { 0 };
+    let card_height = if frame.player.is_some() { 6 } else { 0 };
+    let menu_height = menu_height(frame);
+    // ふしぎのとうの結果のように、何行もある情報も収まるようにする
+    let info_height = frame.info.lines().count().max(2) as u16 + 2;
+    let [error, info, enemies, player, log, menu, help] = Layout::vertical([
+        Constraint::Length(error_height),
+        Constraint::Length(info_height),
+        Constraint::Length(card_height),
+        Constraint::Length(card_height),
+        Constraint::Min(3),
+        Constraint::Length(menu_height),
+        Constraint::Length(1),
+    ])
+    .areas(terminal_frame.area());
+
+    if let Some(message) = &frame.error {
+        let banner = Paragraph::new(format!("エラー：{}　(Enter で たてなおす)", message))
+            .style(Style::new().fg(Color::White).bg(Color::Red))
+            .block(Block::bordered());
+        terminal_frame.render_widget(banner, error);
+    }
+
+    let info_text = Paragraph::new(frame.info.as_str())
+        .wrap(Wrap { trim: false })
+        .block(Block::bordered());
+    terminal_frame.render_widget(info_text, info);
+
+    if !frame.enemies.is_empty() {
+        let constraints =
+            vec![Constraint::Ratio(1, frame.enemies.len() as u32); frame.enemies.len()];
+        let areas = Layout::horizontal(constraints).split(enemies);
+        for (card, area) in frame.enemies.iter().zip(areas.iter()) {
+            draw_card(terminal_frame, card, *area);
+        }
+    }
+    if let Some(card) = &frame.player {
+        draw_card(terminal_frame, card, player);
+    }
+
+    draw_log(terminal_frame, frame, log);
+    draw_menu(terminal_frame, frame, menu);
+
+    let help_text =
+        Paragraph::new("↑↓: えらぶ　Enter: けってい　Esc: もどる　Space: とばす　q: おわる")
+            .style(Style::new().fg(Color::DarkGray));
+    terminal_frame.render_widget(help_text, help);
+}
+
+// カード。ダメージを受けた直後は枠が赤くなる
+fn draw_card(terminal_frame: &mut ratatui::Frame, card: &CardFrame, area: Rect) {
+    let border = if card.hit { Color::Red } else { Color::Gray };
+    let block = Block::bordered()
+        .title(format!("{}　Lv.{}", card.name, card.level))
+        .border_style(Style::new().fg(border));
+    let inner = block.inner(area);
+    terminal_frame.render_widget(block, area);
+
+    let [hp, mp, badges] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(2),
+    ])
+    .areas(inner);
+    let ratio = if card.hp_max > 0.0 {
+        (card.hp / card.hp_max).clamp(0.0, 1.0) as f64
+    } else {
+        0.0
+    };
+    let hp_bar = Gauge::default()
+        .ratio(ratio)
+        .label(format!("HP: {} / {}", card.hp.ceil(), card.hp_max))
+        .gauge_style(Style::new().fg(hp_color(ratio)));
+    terminal_frame.render_widget(hp_bar, hp);
+    if card.mp_max > 0.0 {
+        let mp_text = Paragraph::new(format!("MP: {} / {}", card.mp, card.mp_max));
+        terminal_frame.render_widget(mp_text, mp);
+    }
+
+    let spans: Vec<Span> = card
+        .badges
+        .iter()
+        .flat_map(|(label, kind)| {
+            let color = match kind {
+                BadgeKind::KnockedOut => Color::DarkGray,
+                BadgeKind::SpecialStatus => Color::Magenta,
+                BadgeKind::StatRank => Color::Blue,
+            };
+            [
+                Span::styled(
+                    format!(" {} ", label),
+                    Style::new().fg(Color::White).bg(color),
+                ),
+                Span::raw(" "),
+            ]
+        })
+        .collect();
+    let badge_text = Paragraph::new(Line::from(spans)).wrap(Wrap { trim: false });
+    terminal_frame.render_widget(badge_text, badges);
+}
+
+// HP が少ないほど、黄色から赤に変える
+fn hp_color(ratio: f64) -> Color {
+    if ratio > 0.5 {
+        Color::Green
+    } else if ratio > 0.2 {
+        Color::Yellow
+    } else {
+        Color::Red
+    }
+}
+
+// 戦闘ログ。入りきらないときは、最新の行が見えるように古い行を省く
+fn draw_log(terminal_frame: &mut ratatui::Frame, frame: &Frame, area: Rect) {
+    let block = Block::bordered().title("たたかいの　きろく");
+    let visible = block.inner(area).height as usize;
+    let skip = frame.log.len().saturating_sub(visible);
+    let lines: Vec<Line> = frame
+        .log
+        .iter()
+        .skip(skip)
+        .map(|(line, tone)| {
+            let style = match tone {
+                LogTone::Damage => Style::new().fg(Color::Red),
+                LogTone::Heal => Style::new().fg(Color::Green),
+                LogTone::Status => Style::new().fg(Color::Magenta),
+                LogTone::Normal => Style::new(),
+            };
+            Line::styled(line.as_str(), style)
+        })
+        .collect();
+    terminal_frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+// メニューの高さ。見出しの行数と項目の数に、枠の分を足す
+fn menu_height(frame: &Frame) -> u16 {
+    match &frame.menu {
+        Some(menu) => (menu.title.lines().count() + menu.labels.len() + 2) as u16,
+        None if frame.show_next => 3,
+        None => 0,
+    }
+}
+
+// メニュー。カーソルのある項目に印を付け、選べない項目は灰色にする
+fn draw_menu(terminal_frame: &mut ratatui::Frame, frame: &Frame, area: Rect) {
+    let lines = match &frame.menu {
+        Some(menu) => menu_lines(menu),
+        None if frame.show_next => vec![Line::styled(
+            "▶ つぎへ",
+            Style::new().add_modifier(Modifier::BOLD),
+        )],
+        None => return,
+    };
+    terminal_frame.render_widget(Paragraph::new(lines).block(Block::bordered()), area);
+}
+
+fn menu_lines(menu: &MenuFrame) -> Vec<Line<'_>> {
+    let mut lines: Vec<Line> = menu.title.lines().map(Line::raw).collect();
+    for (idx, (label, selectable)) in menu.labels.iter().zip(&menu.selectable).enumerate() {
+        let line = if menu.cursor == Some(idx) {
+            Line::styled(
+                format!("▶ {}", label),
+                Style::new().add_modifier(Modifier::BOLD),
+            )
+        } else if *selectable {
+            Line::raw(format!("　 {}", label))
+        } else {
+            Line::styled(format!("　 {}", label), Style::new().fg(Color::DarkGray))
+        };
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn test_draw_menu() {
+        let frame = Frame {
+            info: "てきが　あらわれた！".into(),
+            enemies: vec![],
+            player: None,
+            log: vec![],
+            menu: Some(MenuFrame {
+                title: "どうする？".into(),
+                labels: vec!["スキルをつかう".into(), "アイテムをつかう".into()],
+                selectable: vec![true, true],
+                cursor: Some(1),
+            }),
+            error: None,
+            show_next: false,
+        };
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal
+            .draw(|terminal_frame| draw(terminal_frame, &frame))
+            .unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("▶"));
+        assert!(screen.contains("ア"));
+    }
+}
@@ -1,7 +1,9 @@
+#[allow(clippy::module_inception)]
 mod calc_battle_result {
     use crate::{Character, Player};
 
+    #[allow(dead_code)]
     trait CalcBattleResult {
-        fn player_turn(player: Player, enemy: Character) {}
+        fn player_turn(_player: Player, _enemy: Character) {}
     }
 }
@@ -0,0 +1,170 @@
+// キャラクターのカード。
+// 名前・レベル・HP バー・特殊状態を表示し、ダメージを受けた直後は赤く光って揺れる。
+#[allow(clippy::module_inception)]
+mod character_card {
+    use crate::battle_rules::Character;
+    use crate::ParticipantId;
+    #[cfg(feature = "gui")]
+    use iced::widget::{container, progress_bar, text, Column, Row};
+    #[cfg(feature = "gui")]
+    use iced::{Border, Color, Element, Length, Padding};
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+
+    // ダメージを受けたときの演出の長さ
+    const HIT_FEEDBACK_DURATION: Duration = Duration::from_millis(400);
+
+    // ダメージを受けたカードと、演出の開始時刻
+    #[derive(Debug, Clone, Default)]
+    pub struct HitFeedback {
+        started: HashMap<ParticipantId, Instant>,
+        now: Option<Instant>,
+    }
+
+    impl HitFeedback {
+        // 演出を始める
+        pub fn start(&mut self, id: ParticipantId, at: Instant) {
+            self.started.insert(id, at);
+            self.now = Some(at);
+        }
+
+        // 時刻を進め、終わった演出を取り除く
+        pub fn tick(&mut self, now: Instant) {
+            self.now = Some(now);
+            self.started.retain(|_, started| {
+                now.saturating_duration_since(*started) < HIT_FEEDBACK_DURATION
+            });
+        }
+
+        // 演出の進み具合(0.0 から 1.0)。演出中でなければ None
+        pub fn progress(&self, id: ParticipantId) -> Option<f32> {
+            let started = self.started.get(&id)?;
+            let elapsed = self.now?.saturating_duration_since(*started);
+            Some((elapsed.as_secs_f32() / HIT_FEEDBACK_DURATION.as_secs_f32()).min(1.0))
+        }
+
+        pub fn is_active(&self) -> bool {
+            !self.started.is_empty()
+        }
+    }
+
+    // バッジの種類。フロントエンドごとに色を付ける
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum BadgeKind {
+        KnockedOut,
+        SpecialStatus,
+        StatRank,
+    }
+
+    // 倒れていること、特殊状態、能力ランクのバッジ
+    pub fn badges(character: &Character) -> Vec<(String, BadgeKind)> {
+        let mut badges = vec![];
+        if character.is_knocked_out() {
+            badges.push(("たおれた".to_string(), BadgeKind::KnockedOut));
+        }
+        for (special_status, turns) in character.special_statuses() {
+            let label = format!("{} {}", special_status, turns);
+            badges.push((label, BadgeKind::SpecialStatus));
+        }
+        for modifier in &character.stat_modifiers {
+            let label = format!("{}{:+}", modifier.stat, modifier.stage);
+            badges.push((label, BadgeKind::StatRank));
+        }
+        badges
+    }
+
+    // カードを表示する。hit_progress はダメージ演出の進み具合
+    #[cfg(feature = "gui")]
+    pub fn view<'a, Message: 'a>(
+        character: &Character,
+        hit_progress: Option<f32>,
+    ) -> Element<'a, Message> {
+        let mut column = Column::new().spacing(4).width(Length::Fixed(180.0));
+        column = column.push(text!("{}　Lv.{}", character.label(), character.level.value));
+        column = column
+            .push(progress_bar(0.0..=character.hp_max, character.hp).height(Length::Fixed(8.0)));
+        column = column.push(text!("HP: {} / {}", character.hp.ceil(), character.hp_max));
+        if character.mp_max > 0.0 {
+            column = column.push(text!("MP: {} / {}", character.mp, character.mp_max));
+        }
+
+        // 倒れていること、特殊状態、能力ランクをバッジで表示する
+        let mut row = Row::new().spacing(4);
+        for (label, kind) in badges(character) {
+            let color = match kind {
+                BadgeKind::KnockedOut => Color::from_rgb(0.4, 0.4, 0.4),
+                BadgeKind::SpecialStatus => Color::from_rgb(0.6, 0.3, 0.8),
+                BadgeKind::StatRank => Color::from_rgb(0.2, 0.4, 0.8),
+            };
+            row = row.push(badge(label, color));
+        }
+        column = column.push(row);
+
+        // ダメージを受けた直後は背景が赤く光り、左右に揺れる
+        let flash = hit_progress.map_or(0.0, |progress| 0.6 * (1.0 - progress));
+        let shake = hit_progress.map_or(0.0, |progress| {
+            (progress * std::f32::consts::PI * 6.0).sin() * 6.0 * (1.0 - progress)
+        });
+        let card = container(column)
+            .padding(8)
+            .style(move |_theme| container::Style {
+                background: Some(Color::from_rgba(0.9, 0.2, 0.2, flash).into()),
+                border: Border {
+                    width: 1.0,
+                    color: Color::from_rgb(0.5, 0.5, 0.5),
+                    radius: 4.0.into(),
+                },
+                ..Default::default()
+            });
+        container(card)
+            .padding(Padding {
+                top: 0.0,
+                right: 6.0 - shake,
+                bottom: 0.0,
+                left: 6.0 + shake,
+            })
+            .into()
+    }
+
+    #[cfg(feature = "gui")]
+    fn badge<'a, Message: 'a>(label: String, color: Color) -> Element<'a, Message> {
+        container(text(label).size(12).color(Color::WHITE))
+            .padding([0, 4])
+            .style(move |_theme| container::Style {
+                background: Some(color.into()),
+                border: Border {
+                    radius: 4.0.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .into()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_hit_feedback_progress_and_expiry() {
+            let mut feedback = HitFeedback::default();
+            let start = Instant::now();
+            feedback.start(ParticipantId(1), start);
+            assert_eq!(feedback.progress(ParticipantId(1)), Some(0.0));
+            assert_eq!(feedback.progress(ParticipantId::PLAYER), None);
+
+            feedback.tick(start + HIT_FEEDBACK_DURATION / 2);
+            assert_eq!(feedback.progress(ParticipantId(1)), Some(0.5));
+
+            // 演出の長さを過ぎると終わる
+            feedback.tick(start + HIT_FEEDBACK_DURATION);
+            assert!(!feedback.is_active());
+        }
+    }
+}
+
+pub use character_card::badges;
+#[cfg(feature = "gui")]
+pub use character_card::view;
+pub use character_card::BadgeKind;
+pub use character_card::HitFeedback;
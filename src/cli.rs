@@ -0,0 +1,436 @@
+// コマンドラインの引数。
+// サブコマンドがなければウィンドウを開き、あれば画面を使わずに実行して結果を標準出力に書く。
+#[allow(clippy::module_inception)]
+mod cli {
+    use crate::balance;
+    use crate::battle_event::render;
+    use crate::battle_runner::{self, Encounter, Party, Policy, RunError};
+    use crate::simulation::{self, SimulationReport};
+    use crate::tuning::{self, Target, Targets};
+    use crate::{dice, MasterData};
+    use clap::{Args, Parser, Subcommand, ValueEnum};
+    use std::io::{Read, Write};
+    use std::path::{Path, PathBuf};
+
+    #[derive(Debug, Parser)]
+    #[command(name = "rustern-battle", about = "RPG のターン制バトルのようなゲーム")]
+    pub struct Cli {
+        #[command(subcommand)]
+        pub command: Option<Command>,
+    }
+
+    #[derive(Debug, Subcommand)]
+    pub enum Command {
+        /// 戦闘を 1 回、最後まで進めてログを表示する
+        Run(RunArgs),
+        /// シードを変えながら AI どうしで何度も戦闘し、勝率やダメージを集計する
+        Simulate(SimulateArgs),
+        /// 敵を 1 体ずつ相手に何度も戦闘し、レベル・キャラクター・スキルごとの集計を書き出す
+        Report(ReportArgs),
+        /// 敵の hp_max・attack・defence を目標の勝率とターン数に近づけ、マスタデータへの差分を提案する
+        Tune(TuneArgs),
+    }
+
+    #[derive(Debug, Args)]
+    pub struct RunArgs {
+        /// マスタデータの YAML ファイル。省略すると RUSTERN_DIR の example.yml
+        #[arg(long)]
+        data: Option<PathBuf>,
+        /// 乱数のシード。同じシードなら同じ戦闘になる
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// ゆうしゃの覚えるスキル。カンマ区切り。省略するとランダムに選ぶ
+        #[arg(long)]
+        party: Option<String>,
+        /// 出会う敵。「レベル:数」か「@エリア名」か、カンマ区切りの敵の名前
+        #[arg(long)]
+        encounter: String,
+        /// 1 行に「スキル名 敵の番号」を書いたファイル。- なら標準入力。省略すると AI が行動する
+        #[arg(long)]
+        script: Option<PathBuf>,
+        /// 出力の形式
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    }
+
+    #[derive(Debug, Args)]
+    pub struct SimulateArgs {
+        /// マスタデータの YAML ファイル。省略すると RUSTERN_DIR の example.yml
+        #[arg(long)]
+        data: Option<PathBuf>,
+        /// 最初の戦闘のシード。戦闘ごとに 1 ずつ増やす
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// 戦闘の回数
+        #[arg(long, default_value_t = 1000)]
+        battles: usize,
+        /// ゆうしゃの覚えるスキル。カンマ区切り。省略すると戦闘ごとにランダムに選ぶ
+        #[arg(long)]
+        party: Option<String>,
+        /// 出会う敵。「レベル:数」か「@エリア名」か、カンマ区切りの敵の名前
+        #[arg(long)]
+        encounter: String,
+        /// 出力の形式
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    }
+
+    #[derive(Debug, Args)]
+    pub struct ReportArgs {
+        /// マスタデータの YAML ファイル。省略すると RUSTERN_DIR の example.yml
+        #[arg(long)]
+        data: Option<PathBuf>,
+        /// 最初の戦闘のシード。戦闘ごとに 1 ずつ増やす
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// 敵 1 体あたりの戦闘の回数
+        #[arg(long, default_value_t = 200)]
+        battles: usize,
+        /// ゆうしゃの覚えるスキル。カンマ区切り。省略すると戦闘ごとにランダムに選ぶ
+        #[arg(long)]
+        party: Option<String>,
+        /// 出力の形式
+        #[arg(long, value_enum, default_value_t = ReportFormat::Json)]
+        format: ReportFormat,
+        /// CSV に書き出す表。JSON ではすべての表をまとめて書き出す
+        #[arg(long, value_enum, default_value_t = Table::Skills)]
+        table: Table,
+    }
+
+    #[derive(Debug, Args)]
+    pub struct TuneArgs {
+        /// マスタデータの YAML ファイル。省略すると RUSTERN_DIR の example.yml
+        #[arg(long)]
+        data: Option<PathBuf>,
+        /// 最初の戦闘のシード。戦闘ごとに 1 ずつ増やす
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// 1 回の調整で、敵 1 体あたりに行う戦闘の回数
+        #[arg(long, default_value_t = 100)]
+        battles: usize,
+        /// 調整を繰り返す回数の上限
+        #[arg(long, default_value_t = 10)]
+        iterations: usize,
+        /// ゆうしゃの覚えるスキル。カンマ区切り。省略すると戦闘ごとにランダムに選ぶ
+        #[arg(long)]
+        party: Option<String>,
+        /// 目標の勝率。0 から 1
+        #[arg(long, default_value_t = 0.7)]
+        win_rate: f32,
+        /// 目標の、決着までのプレイヤーの平均行動回数
+        #[arg(long, default_value_t = 5.0)]
+        turns: f32,
+        /// レベルごとの目標。「レベル:勝率:ターン数」。何度でも指定できる
+        #[arg(long)]
+        target: Vec<String>,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+    pub enum ReportFormat {
+        Csv,  // 表計算ソフトで読む CSV
+        Json, // プログラムで読む JSON
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+    pub enum Table {
+        Levels,     // 敵のレベルごとの勝率と、倒すまでのターン数
+        Characters, // キャラクターごとの与ダメージ、被ダメージ、特殊状態の割合
+        Skills,     // スキルごとの成功率と、期待ダメージ
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+    pub enum Format {
+        Text, // 人が読む文章
+        Json, // プログラムで読む JSON
+    }
+
+    impl Command {
+        // 実行して、結果を out に書く
+        pub fn execute(&self, out: &mut dyn Write) -> Result<(), RunError> {
+            match self {
+                Command::Run(args) => run(args, out),
+                Command::Simulate(args) => simulate(args, out),
+                Command::Report(args) => report(args, out),
+                Command::Tune(args) => tune(args, out),
+            }
+        }
+    }
+
+    fn run(args: &RunArgs, out: &mut dyn Write) -> Result<(), RunError> {
+        let master_data = load_master_data(args.data.as_deref())?;
+        let party = args.party.as_deref().map(Party::parse).unwrap_or_default();
+        let encounter = Encounter::parse(&args.encounter)?;
+        let policy = match &args.script {
+            Some(path) => Policy::Script(battle_runner::parse_script(&read_script(path)?)?),
+            None => Policy::Ai,
+        };
+
+        dice::seed(args.seed);
+        let record = battle_runner::run_battle(&master_data, &party, &encounter, &policy)?;
+        match args.format {
+            Format::Text => {
+                for line in record.events.iter().filter_map(render) {
+                    writeln!(out, "{}", line)?;
+                }
+                writeln!(out, "{}　({} ターン)", record.outcome, record.turns)?;
+            }
+            Format::Json => write_json(out, &record)?,
+        }
+        Ok(())
+    }
+
+    fn simulate(args: &SimulateArgs, out: &mut dyn Write) -> Result<(), RunError> {
+        let master_data = load_master_data(args.data.as_deref())?;
+        let party = args.party.as_deref().map(Party::parse).unwrap_or_default();
+        let encounter = Encounter::parse(&args.encounter)?;
+
+        let records =
+            simulation::simulate(&master_data, &party, &encounter, args.battles, args.seed)?;
+        let report = SimulationReport::of(&records);
+        match args.format {
+            Format::Text => {
+                for line in report.lines() {
+                    writeln!(out, "{}", line)?;
+                }
+            }
+            Format::Json => write_json(out, &report)?,
+        }
+        Ok(())
+    }
+
+    fn report(args: &ReportArgs, out: &mut dyn Write) -> Result<(), RunError> {
+        let master_data = load_master_data(args.data.as_deref())?;
+        let party = args.party.as_deref().map(Party::parse).unwrap_or_default();
+
+        let report = balance::balance_report(&master_data, &party, args.battles, args.seed)?;
+        let written = match (args.format, args.table) {
+            (ReportFormat::Json, _) => return write_json(out, &report),
+            (ReportFormat::Csv, Table::Levels) => report.levels_csv(out),
+            (ReportFormat::Csv, Table::Characters) => report.characters_csv(out),
+            (ReportFormat::Csv, Table::Skills) => report.skills_csv(out),
+        };
+        written.map_err(|error| RunError::Io(error.to_string()))
+    }
+
+    // 調整の結果を # で始まる行で書き、その後に unified 形式の差分を書く。patch や git apply にそのまま渡せる
+    fn tune(args: &TuneArgs, out: &mut dyn Write) -> Result<(), RunError> {
+        let path = match &args.data {
+            Some(path) => path.clone(),
+            None => crate::default_data_path()?,
+        };
+        let master_data = load_master_data(Some(&path))?;
+        let party = args.party.as_deref().map(Party::parse).unwrap_or_default();
+        let targets = Targets {
+            default: Target {
+                win_rate: args.win_rate,
+                turns: args.turns,
+            },
+            levels: args
+                .target
+                .iter()
+                .map(|spec| Targets::parse_level(spec))
+                .collect::<Result<_, _>>()?,
+        };
+
+        let results = tuning::tune(
+            &master_data,
+            &party,
+            &targets,
+            args.battles,
+            args.iterations,
+            args.seed,
+        )?;
+        for result in &results {
+            writeln!(
+                out,
+                "# {} Lv.{}: hp_max {} → {}, attack {} → {}, defence {} → {}",
+                result.name,
+                result.level,
+                result.before.hp_max,
+                result.after.hp_max,
+                result.before.attack,
+                result.after.attack,
+                result.before.defence,
+                result.after.defence
+            )?;
+            writeln!(
+                out,
+                "#   しょうりりつ {:.1}% (もくひょう {:.1}%)　へいきん {:.2} ターン (もくひょう {})　ちょうせい {} かい",
+                result.win_rate * 100.0,
+                result.target.win_rate * 100.0,
+                result.average_turns,
+                result.target.turns,
+                result.iterations
+            )?;
+        }
+
+        let yaml = std::fs::read_to_string(&path)
+            .map_err(|error| RunError::Load(format!("{}: {}", path.display(), error)))?;
+        let tuned_yaml = tuning::apply_to_yaml(&yaml, &results);
+        // 差分のファイル名は、今いるディレクトリからの相対パスにする
+        let current_dir = std::env::current_dir().unwrap_or_default();
+        let file_name = path
+            .strip_prefix(&current_dir)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        write!(
+            out,
+            "{}",
+            tuning::unified_diff(&file_name, &yaml, &tuned_yaml)
+        )?;
+        Ok(())
+    }
+
+    fn write_json(out: &mut dyn Write, value: &impl serde::Serialize) -> Result<(), RunError> {
+        serde_json::to_writer_pretty(&mut *out, value)
+            .map_err(|error| RunError::Io(error.to_string()))?;
+        writeln!(out)?;
+        Ok(())
+    }
+
+    pub(crate) fn load_master_data(path: Option<&Path>) -> Result<MasterData, RunError> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => crate::default_data_path()?,
+        };
+        MasterData::load(&path).map_err(RunError::Load)
+    }
+
+    fn read_script(path: &Path) -> Result<String, RunError> {
+        let load_error =
+            |error: std::io::Error| RunError::Load(format!("{}: {}", path.display(), error));
+        if path == Path::new("-") {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .map_err(load_error)?;
+            return Ok(text);
+        }
+        std::fs::read_to_string(path).map_err(load_error)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn execute(args: &[&str]) -> Result<String, RunError> {
+            let cli = Cli::try_parse_from(args).unwrap();
+            let mut out = vec![];
+            cli.command.unwrap().execute(&mut out)?;
+            Ok(String::from_utf8(out).unwrap())
+        }
+
+        #[test]
+        fn test_run_prints_log() {
+            let args = [
+                "rustern-battle",
+                "run",
+                "--seed",
+                "3",
+                "--party",
+                "ファイアボール,アクアボール",
+                "--encounter",
+                "スライム",
+            ];
+            let text = execute(&args).unwrap();
+            assert!(text.starts_with("ゆうしゃ の"));
+            assert!(text.trim_end().ends_with("ターン)"));
+            // 同じシードなら、同じログになる
+            assert_eq!(text, execute(&args).unwrap());
+        }
+
+        #[test]
+        fn test_run_prints_json() {
+            let json = execute(&[
+                "rustern-battle",
+                "run",
+                "--party",
+                "ファイアボール",
+                "--encounter",
+                "1:2",
+                "--format",
+                "json",
+            ])
+            .unwrap();
+            let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+            assert!(value["turns"].as_u64().unwrap() > 0);
+            assert_eq!(value["events"][0]["type"], "SkillUsed");
+            assert_eq!(value["events"][0]["user"]["id"], 0);
+            assert_eq!(value["events"][0]["user"]["label"], "ゆうしゃ");
+        }
+
+        #[test]
+        fn test_simulate_prints_report() {
+            let json = execute(&[
+                "rustern-battle",
+                "simulate",
+                "--battles",
+                "10",
+                "--party",
+                "ファイアボール,アクアボール",
+                "--encounter",
+                "スライム",
+                "--format",
+                "json",
+            ])
+            .unwrap();
+            let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(value["battles"], 10);
+            assert!(value["win_rate"].as_f64().unwrap() <= 1.0);
+            assert!(value["skills"].as_array().unwrap().len() >= 2);
+        }
+
+        #[test]
+        fn test_report_prints_csv() {
+            let csv = execute(&[
+                "rustern-battle",
+                "report",
+                "--battles",
+                "3",
+                "--party",
+                "ファイアボール",
+                "--format",
+                "csv",
+                "--table",
+                "levels",
+            ])
+            .unwrap();
+            let mut lines = csv.lines();
+            assert!(lines.next().unwrap().starts_with("level,enemies,battles,"));
+            assert!(lines.next().unwrap().starts_with("1,スライム,3,"));
+        }
+
+        #[test]
+        fn test_tune_prints_diff() {
+            let text = execute(&[
+                "rustern-battle",
+                "tune",
+                "--battles",
+                "5",
+                "--iterations",
+                "2",
+                "--party",
+                "ファイアボール",
+                "--target",
+                "1:0.5:3",
+            ])
+            .unwrap();
+            assert!(text.starts_with("# スライム Lv.1:"));
+            assert!(text.contains("もくひょう 50.0%"));
+            assert!(text.lines().any(|line| line.starts_with("+++ b/")));
+            assert_eq!(
+                execute(&["rustern-battle", "tune", "--target", "1:0.5"]),
+                Err(RunError::BadTarget("1:0.5".into()))
+            );
+        }
+
+        #[test]
+        fn test_run_rejects_unknown_enemy() {
+            let result = execute(&["rustern-battle", "run", "--encounter", "まおう"]);
+            assert_eq!(result, Err(RunError::UnknownEnemy("まおう".into())));
+        }
+    }
+}
+
+pub use cli::Cli;
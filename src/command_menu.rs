@@ -0,0 +1,145 @@
+// キーボードで操作できるコマンドメニュー。
+// 上下キーでカーソルを動かし、Enter で決定、Esc で「もどる」。すべてのサブメニューで使う。
+#[allow(clippy::module_inception)]
+mod command_menu {
+    #[cfg(feature = "gui")]
+    use iced::keyboard::key::{Key, Named};
+    #[cfg(feature = "gui")]
+    use iced::widget::{button, Column};
+    #[cfg(feature = "gui")]
+    use iced::Element;
+
+    // メニューの操作
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum MenuKey {
+        Up,      // カーソルを上へ
+        Down,    // カーソルを下へ
+        Confirm, // 決定
+        Back,    // もどる
+    }
+
+    impl MenuKey {
+        // キーボードのキーをメニューの操作に変換する
+        #[cfg(feature = "gui")]
+        pub fn from_key(key: &Key) -> Option<MenuKey> {
+            match key {
+                Key::Named(Named::ArrowUp) => Some(MenuKey::Up),
+                Key::Named(Named::ArrowDown) => Some(MenuKey::Down),
+                Key::Named(Named::Enter) => Some(MenuKey::Confirm),
+                Key::Named(Named::Escape) => Some(MenuKey::Back),
+                _ => None,
+            }
+        }
+    }
+
+    // メニューの項目。on_select が None の項目は選べない
+    pub struct CommandItem<Message> {
+        pub label: String,
+        pub on_select: Option<Message>,
+    }
+
+    // カーソルを動かした先の項目の番号。選べない項目は飛ばし、端からは反対の端へ回り込む
+    pub fn move_cursor(cursor: Option<usize>, selectable: &[bool], key: MenuKey) -> Option<usize> {
+        let len = selectable.len();
+        if len == 0 {
+            return cursor;
+        }
+        let step = match key {
+            MenuKey::Up => len - 1,
+            MenuKey::Down => 1,
+            MenuKey::Confirm | MenuKey::Back => return cursor,
+        };
+        // 何も選んでいなければ、下なら先頭から、上なら末尾から探す
+        let mut idx = match (cursor, key) {
+            (Some(idx), _) => (idx + step) % len,
+            (None, MenuKey::Up) => len - 1,
+            (None, _) => 0,
+        };
+        for _ in 0..len {
+            if selectable[idx] {
+                return Some(idx);
+            }
+            idx = (idx + step) % len;
+        }
+        cursor
+    }
+
+    // 画面に依存しないメニューの内容。ターミナルなど iced 以外のフロントエンドが描く
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct MenuFrame {
+        pub title: String,
+        pub labels: Vec<String>,
+        pub selectable: Vec<bool>,
+        pub cursor: Option<usize>,
+    }
+
+    // 項目から、画面に依存しないメニューの内容を作る
+    pub fn frame<Message>(
+        title: &str,
+        items: Vec<CommandItem<Message>>,
+        cursor: Option<usize>,
+    ) -> MenuFrame {
+        let (labels, selectable) = items
+            .into_iter()
+            .map(|item| (item.label, item.on_select.is_some()))
+            .unzip();
+        MenuFrame {
+            title: title.to_string(),
+            labels,
+            selectable,
+            cursor,
+        }
+    }
+
+    // 項目を縦に並べる。カーソルのある項目には印を付けて強調する
+    #[cfg(feature = "gui")]
+    pub fn view<'a, Message: Clone + 'a>(
+        items: Vec<CommandItem<Message>>,
+        cursor: Option<usize>,
+    ) -> Element<'a, Message> {
+        let mut column = Column::new();
+        for (idx, item) in items.into_iter().enumerate() {
+            let mut command = if cursor == Some(idx) {
+                button(iced::widget::text!("▶ {}", item.label)).style(button::primary)
+            } else {
+                button(iced::widget::text!("　 {}", item.label)).style(button::secondary)
+            };
+            if let Some(message) = item.on_select {
+                command = command.on_press(message);
+            }
+            column = column.push(command);
+        }
+        column.into()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_move_cursor_wraps() {
+            let selectable = [true, true, true];
+            assert_eq!(move_cursor(None, &selectable, MenuKey::Down), Some(0));
+            assert_eq!(move_cursor(None, &selectable, MenuKey::Up), Some(2));
+            assert_eq!(move_cursor(Some(2), &selectable, MenuKey::Down), Some(0));
+            assert_eq!(move_cursor(Some(0), &selectable, MenuKey::Up), Some(2));
+            assert_eq!(move_cursor(Some(1), &selectable, MenuKey::Confirm), Some(1));
+        }
+
+        #[test]
+        fn test_move_cursor_skips_unselectable() {
+            let selectable = [true, false, true];
+            assert_eq!(move_cursor(Some(0), &selectable, MenuKey::Down), Some(2));
+            assert_eq!(move_cursor(Some(2), &selectable, MenuKey::Up), Some(0));
+            assert_eq!(move_cursor(None, &[false, false], MenuKey::Down), None);
+        }
+    }
+}
+
+pub use command_menu::frame;
+pub use command_menu::move_cursor;
+#[cfg(feature = "gui")]
+pub use command_menu::view;
+pub use command_menu::CommandItem;
+pub use command_menu::MenuFrame;
+pub use command_menu::MenuKey;
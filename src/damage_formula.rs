@@ -0,0 +1,123 @@
+// ダメージ計算式。
+// マスタデータの ruleset で切り替えられるため、再コンパイルせずにバランス調整を試せる。
+#[allow(clippy::module_inception)]
+mod damage_formula {
+    use crate::battle_rules::Character;
+    use crate::Power;
+    use serde::Deserialize;
+
+    pub trait DamageFormula {
+        // 乱数・威力・攻撃側・防御側からダメージを計算する
+        fn damage(&self, random: f32, power: &Power, from: &Character, to: &Character) -> f32;
+    }
+
+    // 乗算式(従来の計算式)。乱数 * 威力 * 攻撃力 / 防御力
+    // 攻撃力・防御力は能力ランクや特殊状態を反映した値を使う
+    pub struct Multiplicative;
+
+    impl DamageFormula for Multiplicative {
+        fn damage(&self, random: f32, power: &Power, from: &Character, to: &Character) -> f32 {
+            random * power.value * from.attack() / to.defence()
+        }
+    }
+
+    // 減算式。乱数 * 威力 * (攻撃力 - 防御力 / 2)
+    // 防御力が攻撃力の2倍以上ならダメージは 0 になる
+    pub struct Subtractive;
+
+    impl DamageFormula for Subtractive {
+        fn damage(&self, random: f32, power: &Power, from: &Character, to: &Character) -> f32 {
+            let value = from.attack() - to.defence() / 2.0;
+            if value <= 0.0 {
+                return 0.0;
+            }
+            random * power.value * value
+        }
+    }
+
+    // レベル補正式。乗算式に (1 + 攻撃側のレベル / 10) を掛ける
+    pub struct LevelScaled;
+
+    impl DamageFormula for LevelScaled {
+        fn damage(&self, random: f32, power: &Power, from: &Character, to: &Character) -> f32 {
+            let level_rate = 1.0 + from.level.value as f32 / 10.0;
+            Multiplicative.damage(random, power, from, to) * level_rate
+        }
+    }
+
+    // マスタデータで指定するダメージ計算式の種類
+    #[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+    pub enum DamageFormulaType {
+        #[default]
+        Multiplicative, // 乗算式
+        Subtractive, // 減算式
+        LevelScaled, // レベル補正式
+    }
+
+    impl DamageFormulaType {
+        pub fn formula(&self) -> Box<dyn DamageFormula> {
+            match self {
+                DamageFormulaType::Multiplicative => Box::new(Multiplicative),
+                DamageFormulaType::Subtractive => Box::new(Subtractive),
+                DamageFormulaType::LevelScaled => Box::new(LevelScaled),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn character(level: u8, attack: f32, defence: f32) -> Character {
+            Character::for_test(level, attack, defence)
+        }
+
+        #[test]
+        fn test_multiplicative() {
+            let power = Power { value: 10.0 };
+            let damage = Multiplicative.damage(
+                1.0,
+                &power,
+                &character(1, 6.0, 0.0),
+                &character(1, 0.0, 2.0),
+            );
+            assert_eq!(damage, 30.0);
+        }
+
+        #[test]
+        fn test_subtractive() {
+            let power = Power { value: 10.0 };
+            let from = character(1, 6.0, 0.0);
+            assert_eq!(
+                Subtractive.damage(1.0, &power, &from, &character(1, 0.0, 2.0)),
+                50.0
+            );
+            // 防御力が攻撃力の2倍以上ならダメージを与えられない
+            assert_eq!(
+                Subtractive.damage(1.0, &power, &from, &character(1, 0.0, 20.0)),
+                0.0
+            );
+        }
+
+        #[test]
+        fn test_level_scaled() {
+            let power = Power { value: 10.0 };
+            let damage = LevelScaled.damage(
+                1.0,
+                &power,
+                &character(5, 6.0, 0.0),
+                &character(1, 0.0, 2.0),
+            );
+            assert_eq!(damage, 45.0);
+        }
+
+        #[test]
+        fn test_damage_formula_type_from_yaml() {
+            let formula: DamageFormulaType = serde_yaml::from_str("Subtractive").unwrap();
+            assert_eq!(formula, DamageFormulaType::Subtractive);
+        }
+    }
+}
+
+pub use damage_formula::DamageFormula;
+pub use damage_formula::DamageFormulaType;
@@ -0,0 +1,61 @@
+// ゲームで使う乱数。
+// ふだんは遊ぶたびにちがう結果になり、シードを決めると同じ戦闘を再現できる。
+// スレッドごとに持つので、並べて動かすテストやシミュレーションが互いに影響しない。
+#[allow(clippy::module_inception)]
+mod dice {
+    use rand::rngs::StdRng;
+    use rand::{RngCore, SeedableRng};
+    use std::cell::RefCell;
+
+    thread_local! {
+        static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+    }
+
+    // このスレッドの以降の乱数を、シードから作り直す
+    pub fn seed(seed: u64) {
+        RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+    }
+
+    // 乱数を作る。rand::thread_rng() の代わりに使う
+    pub fn rng() -> Dice {
+        Dice
+    }
+
+    pub struct Dice;
+
+    impl RngCore for Dice {
+        fn next_u32(&mut self) -> u32 {
+            RNG.with(|rng| rng.borrow_mut().next_u32())
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            RNG.with(|rng| rng.borrow_mut().next_u64())
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            RNG.with(|rng| rng.borrow_mut().fill_bytes(dest))
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            RNG.with(|rng| rng.borrow_mut().try_fill_bytes(dest))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use rand::Rng;
+
+        #[test]
+        fn test_same_seed_same_rolls() {
+            seed(42);
+            let first: Vec<u32> = (0..5).map(|_| rng().gen_range(0..100)).collect();
+            seed(42);
+            let second: Vec<u32> = (0..5).map(|_| rng().gen_range(0..100)).collect();
+            assert_eq!(first, second);
+        }
+    }
+}
+
+pub use dice::rng;
+pub use dice::seed;
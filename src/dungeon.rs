@@ -0,0 +1,191 @@
+// ダンジョンの探索。
+// 部屋と部屋をつなぐ通路を進み、1 歩ごとに遭遇率で敵と出会う。
+// たからばこからはアイテムが手に入り、奥のボスの部屋ではボスと戦う。
+#[allow(clippy::module_inception)]
+mod dungeon {
+    use crate::game_error::GameError;
+    use crate::{dice, Rarity};
+    use rand::Rng;
+    use serde::Deserialize;
+    use std::collections::BTreeSet;
+
+    // ダンジョンの集まり
+    #[derive(Debug, Clone, Default, Deserialize)]
+    pub struct Dungeons {
+        pub dungeons: Vec<Dungeon>,
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct Dungeon {
+        pub name: String,
+        pub encounter_table: String, // 歩いているときに出会う敵の遭遇表
+        pub encounter_rate: f32,     // 1 歩ごとに敵と出会う確率(0.0 から 1.0)
+        pub rooms: Vec<Room>,        // 先頭の部屋から探索を始める
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct Room {
+        pub name: String,
+        pub exits: Vec<String>, // 進める部屋の名前
+        #[serde(default)]
+        pub contents: RoomContents,
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+    pub enum RoomContents {
+        #[default]
+        Empty,
+        Chest(Rarity),     // このレアリティのアイテムが 1 つ入ったたからばこ
+        Boss(Vec<String>), // ボスの部屋。入るとこの敵と戦う
+    }
+
+    // 部屋に進んだ結果
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Step {
+        Quiet,             // 何も起きなかった
+        Encounter,         // 遭遇表の敵と出会った
+        Boss(Vec<String>), // ボスと出会った
+    }
+
+    impl Dungeons {
+        pub fn find(&self, name: &str) -> Option<&Dungeon> {
+            self.dungeons.iter().find(|dungeon| dungeon.name == name)
+        }
+    }
+
+    // 探索中のダンジョンと、いる部屋
+    #[derive(Debug, Clone)]
+    pub struct Exploration {
+        pub dungeon: Dungeon,
+        room: usize,
+        opened: BTreeSet<String>, // 開けたたからばこのある部屋
+        pub steps: u32,           // 歩いた数
+    }
+
+    impl Exploration {
+        pub fn new(dungeon: Dungeon) -> Self {
+            Exploration {
+                dungeon,
+                room: 0,
+                opened: BTreeSet::new(),
+                steps: 0,
+            }
+        }
+
+        pub fn room(&self) -> &Room {
+            &self.dungeon.rooms[self.room]
+        }
+
+        // まだ開けていないたからばこがあるか
+        pub fn has_chest(&self) -> bool {
+            matches!(self.room().contents, RoomContents::Chest(_))
+                && !self.opened.contains(&self.room().name)
+        }
+
+        // 部屋の様子
+        pub fn describe(&self) -> String {
+            let mut description = format!("{}　{}", self.dungeon.name, self.room().name);
+            if self.has_chest() {
+                description.push_str("。たからばこが　ある。");
+            }
+            description
+        }
+
+        // となりの部屋に進む。ボスの部屋でなければ、遭遇率で敵と出会う
+        pub fn move_to(&mut self, exit: &str) -> Result<Step, GameError> {
+            if !self.room().exits.iter().any(|name| name == exit) {
+                return Err(GameError::NoSuchRoom(exit.to_string()));
+            }
+            self.room = self
+                .dungeon
+                .rooms
+                .iter()
+                .position(|room| room.name == exit)
+                .ok_or_else(|| GameError::NoSuchRoom(exit.to_string()))?;
+            self.steps += 1;
+            if let RoomContents::Boss(bosses) = &self.room().contents {
+                return Ok(Step::Boss(bosses.clone()));
+            }
+            let encountered = dice::rng().gen::<f32>() < self.dungeon.encounter_rate;
+            Ok(if encountered {
+                Step::Encounter
+            } else {
+                Step::Quiet
+            })
+        }
+
+        // たからばこを開けて、中身のレアリティを返す。開けたたからばこは空になる
+        pub fn open_chest(&mut self) -> Option<Rarity> {
+            if !self.has_chest() {
+                return None;
+            }
+            self.opened.insert(self.room().name.clone());
+            match &self.room().contents {
+                RoomContents::Chest(rarity) => Some(rarity.clone()),
+                _ => None,
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn dungeon(encounter_rate: f32) -> Dungeon {
+            let room = |name: &str, exits: &[&str], contents| Room {
+                name: name.into(),
+                exits: exits.iter().map(|exit| exit.to_string()).collect(),
+                contents,
+            };
+            Dungeon {
+                name: "どうくつ".into(),
+                encounter_table: "どうくつ".into(),
+                encounter_rate,
+                rooms: vec![
+                    room("いりぐち", &["ひろま"], RoomContents::Empty),
+                    room(
+                        "ひろま",
+                        &["いりぐち", "おく"],
+                        RoomContents::Chest(Rarity::new(1)),
+                    ),
+                    room("おく", &[], RoomContents::Boss(vec!["オーク".into()])),
+                ],
+            }
+        }
+
+        #[test]
+        fn test_move_rolls_encounters() {
+            let mut quiet = Exploration::new(dungeon(0.0));
+            assert_eq!(quiet.move_to("ひろま"), Ok(Step::Quiet));
+            let mut always = Exploration::new(dungeon(1.0));
+            assert_eq!(always.move_to("ひろま"), Ok(Step::Encounter));
+
+            // つながっていない部屋には進めず、ボスの部屋では必ずボスと出会う
+            assert_eq!(
+                quiet.move_to("いりぐち2"),
+                Err(GameError::NoSuchRoom("いりぐち2".into()))
+            );
+            assert_eq!(quiet.move_to("おく"), Ok(Step::Boss(vec!["オーク".into()])));
+            assert_eq!(quiet.steps, 2);
+        }
+
+        #[test]
+        fn test_chest_opens_once() {
+            let mut exploration = Exploration::new(dungeon(0.0));
+            assert_eq!(exploration.open_chest(), None);
+            exploration.move_to("ひろま").unwrap();
+            assert!(exploration.describe().ends_with("たからばこが　ある。"));
+            assert_eq!(exploration.open_chest(), Some(Rarity::new(1)));
+            assert_eq!(exploration.open_chest(), None);
+
+            // 部屋を出て戻っても、空のまま
+            exploration.move_to("いりぐち").unwrap();
+            exploration.move_to("ひろま").unwrap();
+            assert!(!exploration.has_chest());
+        }
+    }
+}
+
+pub use dungeon::Dungeons;
+pub use dungeon::Exploration;
+pub use dungeon::Step;
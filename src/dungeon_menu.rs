@@ -0,0 +1,130 @@
+// ダンジョンの探索メニュー。
+// いる部屋からつながっている部屋へ進むか、たからばこを開けるかを選ぶ。
+#[allow(clippy::module_inception)]
+mod dungeon_menu {
+    use crate::command_menu::{self, CommandItem, MenuFrame, MenuKey};
+    use crate::dungeon::Exploration;
+    #[cfg(feature = "gui")]
+    use iced::widget::Column;
+    #[cfg(feature = "gui")]
+    use iced::Element;
+
+    #[derive(Debug, Clone)]
+    pub enum Message {
+        OnSelectCommand(Command),
+        OnClickNext,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Command {
+        Move(String), // となりの部屋へ進む
+        OpenChest,    // たからばこを開ける
+    }
+
+    const TITLE: &str = "どちらへ　すすむ？";
+
+    impl std::fmt::Display for Command {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                Command::Move(room) => write!(f, "{}へ　すすむ", room),
+                Command::OpenChest => write!(f, "たからばこを　あける"),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct DungeonMenu {
+        commands: Vec<Command>,
+        // 選択しているコマンド
+        pub(crate) command: Option<Command>,
+    }
+
+    impl DungeonMenu {
+        pub fn new(exploration: &Exploration) -> Self {
+            let mut commands: Vec<Command> = exploration
+                .room()
+                .exits
+                .iter()
+                .map(|room| Command::Move(room.clone()))
+                .collect();
+            if exploration.has_chest() {
+                commands.insert(0, Command::OpenChest);
+            }
+            DungeonMenu {
+                commands,
+                command: None,
+            }
+        }
+
+        pub fn update(&mut self, message: Message) {
+            match message {
+                Message::OnSelectCommand(command) => {
+                    // 選択しているコマンドを更新する
+                    self.command = Some(command);
+                }
+                Message::OnClickNext => {
+                    // 何もしない
+                }
+            }
+        }
+
+        // 選択しているコマンドの番号
+        fn cursor(&self) -> Option<usize> {
+            let command = self.command.as_ref()?;
+            self.commands.iter().position(|c| c == command)
+        }
+
+        // コマンドメニューの項目
+        fn items(&self) -> Vec<CommandItem<Message>> {
+            self.commands
+                .iter()
+                .map(|command| CommandItem {
+                    label: command.to_string(),
+                    on_select: Some(Message::OnSelectCommand(command.clone())),
+                })
+                .collect()
+        }
+
+        // キーボードの操作を、このメニューのメッセージに変換する
+        pub fn on_key(&self, key: MenuKey) -> Option<Message> {
+            match key {
+                MenuKey::Up | MenuKey::Down => {
+                    let selectable = vec![true; self.commands.len()];
+                    let idx = command_menu::move_cursor(self.cursor(), &selectable, key)?;
+                    Some(Message::OnSelectCommand(self.commands[idx].clone()))
+                }
+                MenuKey::Confirm => self.command.as_ref().map(|_| Message::OnClickNext),
+                // 来た道は、部屋を選んで戻る
+                MenuKey::Back => None,
+            }
+        }
+
+        #[cfg(feature = "gui")]
+        pub fn view(&self) -> Element<'_, Message> {
+            let mut column = Column::new();
+
+            column = column.push(TITLE);
+
+            // コマンドメニューの作成
+            column = column.push(command_menu::view(self.items(), self.cursor()));
+
+            let confirm = iced::widget::button("これに　する");
+            match self.command {
+                // コマンドが選択されている場合だけ、次へ進める
+                Some(_) => column = column.push(confirm.on_press(Message::OnClickNext)),
+                None => column = column.push(confirm),
+            }
+
+            column.into()
+        }
+
+        // 画面に依存しないメニューの内容
+        pub fn frame(&self) -> MenuFrame {
+            command_menu::frame(TITLE, self.items(), self.cursor())
+        }
+    }
+}
+
+pub use dungeon_menu::Command;
+pub use dungeon_menu::DungeonMenu;
+pub use dungeon_menu::Message;
@@ -0,0 +1,199 @@
+// エリアごとの敵との遭遇表。
+// 重みつきの敵の組み合わせから 1 つを選ぶ。同じ敵の見分けは Characters::for_battle で付ける。
+#[allow(clippy::module_inception)]
+mod encounter_table {
+    use crate::battle_rules::Character;
+    use crate::game_error::GameError;
+    use crate::{dice, Characters, Level};
+    use rand::seq::SliceRandom;
+    use serde::Deserialize;
+
+    // 遭遇表の集まり
+    #[derive(Debug, Clone, Default, Deserialize)]
+    pub struct EncounterTables {
+        pub tables: Vec<EncounterTable>,
+    }
+
+    // 1 つのエリアの遭遇表
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct EncounterTable {
+        pub area: String,
+        pub groups: Vec<EncounterGroup>,
+    }
+
+    // 敵の組み合わせ。weight が大きいほど選ばれやすい
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct EncounterGroup {
+        pub weight: u32,
+        pub enemies: GroupEnemies,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    pub enum GroupEnemies {
+        Named(Vec<String>), // 名前で決める。同じ名前を並べてもよい
+        Levels {
+            // レベルの範囲から、同じ敵が重なってもよいので count 体選ぶ
+            min: Level,
+            max: Level,
+            count: usize,
+        },
+    }
+
+    impl EncounterTables {
+        // エリアの遭遇表を振って、出会う敵を返す
+        pub fn roll(
+            &self,
+            area: &str,
+            characters: &Characters,
+        ) -> Result<Vec<Character>, GameError> {
+            let table = self
+                .tables
+                .iter()
+                .find(|table| table.area == area)
+                .ok_or_else(|| GameError::UnknownArea(area.to_string()))?;
+            table.roll(characters)
+        }
+    }
+
+    impl EncounterTable {
+        pub fn roll(&self, characters: &Characters) -> Result<Vec<Character>, GameError> {
+            let mut rng = dice::rng();
+            let group = self
+                .groups
+                .choose_weighted(&mut rng, |group| group.weight)
+                .map_err(|_| GameError::NoEncounteredEnemies)?;
+            let enemies = group.enemies.pick(characters)?;
+            if enemies.is_empty() {
+                return Err(GameError::NoEncounteredEnemies);
+            }
+            Ok(enemies)
+        }
+    }
+
+    impl GroupEnemies {
+        pub fn pick(&self, characters: &Characters) -> Result<Vec<Character>, GameError> {
+            match self {
+                GroupEnemies::Named(names) => names
+                    .iter()
+                    .map(|name| {
+                        characters
+                            .characters
+                            .iter()
+                            .find(|enemy| &enemy.name == name)
+                            .cloned()
+                            .ok_or_else(|| GameError::UnknownEnemy(name.clone()))
+                    })
+                    .collect(),
+                GroupEnemies::Levels { min, max, count } => {
+                    let candidates: Vec<&Character> = characters
+                        .characters
+                        .iter()
+                        .filter(|enemy| (min.value..=max.value).contains(&enemy.level.value))
+                        .collect();
+                    let mut rng = dice::rng();
+                    Ok((0..*count)
+                        .filter_map(|_| candidates.choose(&mut rng))
+                        .map(|enemy| (*enemy).clone())
+                        .collect())
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn characters() -> Characters {
+            let enemy = |name: &str, level: u8| {
+                let mut enemy = Character::for_test(level, 1.0, 1.0);
+                enemy.name = name.into();
+                enemy
+            };
+            Characters {
+                characters: vec![
+                    enemy("スライム", 1),
+                    enemy("ゴブリン", 2),
+                    enemy("オーク", 3),
+                ],
+            }
+        }
+
+        fn labels(enemies: &Characters) -> Vec<&str> {
+            enemies
+                .characters
+                .iter()
+                .map(|enemy| enemy.label())
+                .collect()
+        }
+
+        #[test]
+        fn test_named_group_labels_duplicates() {
+            let table = EncounterTable {
+                area: "そうげん".into(),
+                groups: vec![EncounterGroup {
+                    weight: 1,
+                    enemies: GroupEnemies::Named(vec![
+                        "スライム".into(),
+                        "ゴブリン".into(),
+                        "スライム".into(),
+                    ]),
+                }],
+            };
+            let enemies = Characters::for_battle(table.roll(&characters()).unwrap());
+            assert_eq!(labels(&enemies), vec!["スライムA", "ゴブリン", "スライムB"]);
+        }
+
+        #[test]
+        fn test_level_range_group() {
+            let table = EncounterTable {
+                area: "どうくつ".into(),
+                groups: vec![
+                    EncounterGroup {
+                        weight: 0,
+                        enemies: GroupEnemies::Named(vec!["オーク".into()]),
+                    },
+                    EncounterGroup {
+                        weight: 1,
+                        enemies: GroupEnemies::Levels {
+                            min: Level::new(1),
+                            max: Level::new(2),
+                            count: 3,
+                        },
+                    },
+                ],
+            };
+            for seed in 0..20 {
+                dice::seed(seed);
+                let enemies = table.roll(&characters()).unwrap();
+                // 重みが 0 の組み合わせは選ばれず、足りなくても数は減らない
+                assert_eq!(enemies.len(), 3);
+                assert!(enemies.iter().all(|enemy| enemy.level.value <= 2));
+            }
+        }
+
+        #[test]
+        fn test_roll_errors() {
+            let tables = EncounterTables {
+                tables: vec![EncounterTable {
+                    area: "まおうのしろ".into(),
+                    groups: vec![EncounterGroup {
+                        weight: 1,
+                        enemies: GroupEnemies::Named(vec!["まおう".into()]),
+                    }],
+                }],
+            };
+            assert_eq!(
+                tables.roll("まおうのしろ", &characters()).unwrap_err(),
+                GameError::UnknownEnemy("まおう".into())
+            );
+            assert_eq!(
+                tables.roll("うみ", &characters()).unwrap_err(),
+                GameError::UnknownArea("うみ".into())
+            );
+        }
+    }
+}
+
+pub use encounter_table::EncounterTables;
+pub use encounter_table::GroupEnemies;
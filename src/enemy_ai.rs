@@ -0,0 +1,116 @@
+// 敵の行動を決める AI。
+#[allow(clippy::module_inception)]
+mod enemy_ai {
+    use crate::battle_rules::Character;
+    use crate::dice;
+    use crate::{Effect, Skill};
+    use rand::seq::SliceRandom;
+
+    // ため中ならそのスキルを、そうでなければ使えるスキルの中からランダムに選ぶ。
+    // 使えるスキルがなければ None を返す
+    pub fn choose_skill(enemy: &Character) -> Option<Skill> {
+        if let Some(charging) = &enemy.charging {
+            return Some(charging.skill.clone());
+        }
+        let usable_skills: Vec<&Skill> = enemy
+            .skills
+            .skills
+            .iter()
+            .filter(|skill| enemy.can_use(skill) && !is_revive(skill))
+            .collect();
+
+        let mut rng = dice::rng();
+        usable_skills.choose(&mut rng).map(|skill| (*skill).clone())
+    }
+
+    // 倒れた味方がいて、生き返らせるスキルが使えるなら、そのスキルと味方の番号を返す
+    pub fn choose_revive(enemy: &Character, allies: &[Character]) -> Option<(Skill, usize)> {
+        if enemy.charging.is_some() {
+            return None;
+        }
+        let ally_idx = allies.iter().position(|ally| ally.is_knocked_out())?;
+        let skill = enemy
+            .skills
+            .skills
+            .iter()
+            .find(|skill| is_revive(skill) && enemy.can_use(skill))?;
+        Some((skill.clone(), ally_idx))
+    }
+
+    fn is_revive(skill: &Skill) -> bool {
+        matches!(skill.effect, Effect::Revive(_))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::battle_rules::Charging;
+        use crate::{MpCost, Power, Probability, Rarity, Ratio, Skills};
+
+        fn skill(name: &str, mp_cost: f32) -> Skill {
+            Skill {
+                name: name.into(),
+                rarity: Rarity::new(1),
+                effect: Effect::Attack(Probability { percentage: 100 }, Power { value: 1.0 }),
+                mp_cost: MpCost { value: mp_cost },
+                cooldown: 0,
+                charge_turns: 0,
+                additional_effects: vec![],
+            }
+        }
+
+        fn enemy(mp: f32, skills: Vec<Skill>) -> Character {
+            let mut enemy = Character::for_test(1, 1.0, 1.0);
+            enemy.mp = mp;
+            enemy.mp_max = 10.0;
+            enemy.skills = Skills { skills };
+            enemy
+        }
+
+        #[test]
+        fn test_choose_skill_respects_mp() {
+            let enemy = enemy(3.0, vec![skill("ブレス", 10.0), skill("かみつく", 0.0)]);
+            for _ in 0..20 {
+                assert_eq!(choose_skill(&enemy).unwrap().name, "かみつく");
+            }
+        }
+
+        #[test]
+        fn test_choose_skill_while_charging() {
+            let mut enemy = enemy(0.0, vec![skill("ブレス", 10.0), skill("かみつく", 0.0)]);
+            enemy.charging = Some(Charging {
+                skill: skill("ブレス", 10.0),
+                turns: 1,
+            });
+            assert_eq!(choose_skill(&enemy).unwrap().name, "ブレス");
+        }
+
+        #[test]
+        fn test_choose_skill_without_mp() {
+            let enemy = enemy(0.0, vec![skill("ブレス", 10.0)]);
+            assert_eq!(choose_skill(&enemy), None);
+        }
+
+        #[test]
+        fn test_choose_revive() {
+            let mut revive = skill("ザオラル", 0.0);
+            revive.effect = Effect::Revive(Ratio { percentage: 0.5 });
+            let enemy = enemy(0.0, vec![skill("かみつく", 0.0), revive]);
+            let mut fallen = enemy.clone();
+            fallen.hp_decrease(fallen.hp_max);
+
+            // 倒れた味方がいなければ生き返らせない
+            assert_eq!(choose_revive(&enemy, std::slice::from_ref(&enemy)), None);
+            let (skill, ally_idx) = choose_revive(&enemy, &[enemy.clone(), fallen]).unwrap();
+            assert_eq!(skill.name, "ザオラル");
+            assert_eq!(ally_idx, 1);
+            // 攻撃のときは生き返らせるスキルを選ばない
+            for _ in 0..20 {
+                assert_eq!(choose_skill(&enemy).unwrap().name, "かみつく");
+            }
+        }
+    }
+}
+
+pub use enemy_ai::choose_revive;
+pub use enemy_ai::choose_skill;
@@ -0,0 +1,122 @@
+// そうびのメニュー。
+// 身につけているそうびと持っているそうびを並べ、選んだそうびを付け外しする。
+#[allow(clippy::module_inception)]
+mod equip_menu {
+    use crate::command_menu::{self, CommandItem, MenuFrame, MenuKey};
+    use crate::equipment::{Equipment, Equipped, Slot};
+    #[cfg(feature = "gui")]
+    use iced::widget::Column;
+    #[cfg(feature = "gui")]
+    use iced::Element;
+
+    #[derive(Debug, Clone)]
+    pub enum Message {
+        OnSelectCommand(Command),
+        OnClickNext,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Command {
+        Unequip(Slot), // 身につけているそうびを外す
+        Equip(String), // 持っているそうびを身につける
+        Done,          // そうびを終える
+    }
+
+    const TITLE: &str = "そうびを　えらぼう";
+
+    #[derive(Debug, Clone)]
+    pub struct EquipMenu {
+        commands: Vec<(Command, String)>, // コマンドと、その表示
+        // 選択しているコマンド
+        pub(crate) command: Option<Command>,
+    }
+
+    impl EquipMenu {
+        pub fn new(equipped: &Equipped, owned: &[Equipment]) -> Self {
+            let unequip = equipped.iter().map(|equipment| {
+                let label = format!("E {}", equipment);
+                (Command::Unequip(equipment.slot), label)
+            });
+            let equip = owned.iter().map(|equipment| {
+                let label = format!("　{}", equipment);
+                (Command::Equip(equipment.name.clone()), label)
+            });
+            let done = (Command::Done, "おわる".to_string());
+            EquipMenu {
+                commands: unequip.chain(equip).chain([done]).collect(),
+                command: None,
+            }
+        }
+
+        pub fn update(&mut self, message: Message) {
+            match message {
+                Message::OnSelectCommand(command) => {
+                    // 選択しているコマンドを更新する
+                    self.command = Some(command);
+                }
+                Message::OnClickNext => {
+                    // 何もしない
+                }
+            }
+        }
+
+        // 選択しているコマンドの番号
+        fn cursor(&self) -> Option<usize> {
+            let command = self.command.as_ref()?;
+            self.commands.iter().position(|(c, _)| c == command)
+        }
+
+        // コマンドメニューの項目
+        fn items(&self) -> Vec<CommandItem<Message>> {
+            self.commands
+                .iter()
+                .map(|(command, label)| CommandItem {
+                    label: label.clone(),
+                    on_select: Some(Message::OnSelectCommand(command.clone())),
+                })
+                .collect()
+        }
+
+        // キーボードの操作を、このメニューのメッセージに変換する
+        pub fn on_key(&self, key: MenuKey) -> Option<Message> {
+            match key {
+                MenuKey::Up | MenuKey::Down => {
+                    let selectable = vec![true; self.commands.len()];
+                    let idx = command_menu::move_cursor(self.cursor(), &selectable, key)?;
+                    Some(Message::OnSelectCommand(self.commands[idx].0.clone()))
+                }
+                MenuKey::Confirm => self.command.as_ref().map(|_| Message::OnClickNext),
+                // もどるときは「おわる」を選ぶ
+                MenuKey::Back => Some(Message::OnSelectCommand(Command::Done)),
+            }
+        }
+
+        #[cfg(feature = "gui")]
+        pub fn view(&self) -> Element<'_, Message> {
+            let mut column = Column::new();
+
+            column = column.push(TITLE);
+
+            // コマンドメニューの作成
+            column = column.push(command_menu::view(self.items(), self.cursor()));
+
+            let confirm = iced::widget::button("これに　する");
+            match self.command {
+                // コマンドが選択されている場合だけ、次へ進める
+                Some(_) => column = column.push(confirm.on_press(Message::OnClickNext)),
+                None => column = column.push(confirm),
+            }
+
+            column.into()
+        }
+
+        // 画面に依存しないメニューの内容
+        pub fn frame(&self) -> MenuFrame {
+            command_menu::frame(TITLE, self.items(), self.cursor())
+        }
+    }
+}
+
+pub use equip_menu::Command;
+pub use equip_menu::EquipMenu;
+pub use equip_menu::Message;
@@ -0,0 +1,173 @@
+// そうび。
+// ぶき・よろい・アクセサリをひとつずつ身につけられ、こうげきりょくやぼうぎょりょくが上がる。
+// 特殊状態を防いだり、身につけている間だけ使えるスキルを覚えたりするそうびもある。
+#[allow(clippy::module_inception)]
+mod equipment {
+    use crate::{Rarity, SpecialStatusType};
+    use serde::Deserialize;
+
+    // そうびを身につける場所
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+    pub enum Slot {
+        Weapon,    // ぶき
+        Armor,     // よろい
+        Accessory, // アクセサリ
+    }
+
+    impl std::fmt::Display for Slot {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                Slot::Weapon => write!(f, "ぶき"),
+                Slot::Armor => write!(f, "よろい"),
+                Slot::Accessory => write!(f, "アクセサリ"),
+            }
+        }
+    }
+
+    // そうびの集まり
+    #[derive(Debug, Clone, Default, Deserialize)]
+    pub struct Equipments {
+        pub equipments: Vec<Equipment>,
+    }
+
+    impl Equipments {
+        pub fn find(&self, name: &str) -> Option<&Equipment> {
+            self.equipments
+                .iter()
+                .find(|equipment| equipment.name == name)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    pub struct Equipment {
+        pub name: String,
+        pub rarity: Rarity,
+        pub slot: Slot,
+        #[serde(default)]
+        pub attack: f32, // こうげきりょくに足す値
+        #[serde(default)]
+        pub defence: f32, // ぼうぎょりょくに足す値
+        #[serde(default)]
+        pub immunities: Vec<SpecialStatusType>, // かからなくなる特殊状態
+        #[serde(default)]
+        pub skills: Vec<String>, // 身につけている間だけ使えるスキルの名前
+    }
+
+    impl std::fmt::Display for Equipment {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "{}：{}", self.slot, self.name)
+        }
+    }
+
+    // 身につけているそうび
+    #[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+    pub struct Equipped {
+        #[serde(default)]
+        pub weapon: Option<Equipment>,
+        #[serde(default)]
+        pub armor: Option<Equipment>,
+        #[serde(default)]
+        pub accessory: Option<Equipment>,
+    }
+
+    impl Equipped {
+        fn slot_mut(&mut self, slot: Slot) -> &mut Option<Equipment> {
+            match slot {
+                Slot::Weapon => &mut self.weapon,
+                Slot::Armor => &mut self.armor,
+                Slot::Accessory => &mut self.accessory,
+            }
+        }
+
+        // 身につけているそうびを、ぶき・よろい・アクセサリの順に並べる
+        pub fn iter(&self) -> impl Iterator<Item = &Equipment> {
+            [&self.weapon, &self.armor, &self.accessory]
+                .into_iter()
+                .flatten()
+        }
+
+        // そうびを身につけ、それまで身につけていた同じ場所のそうびを返す
+        pub fn equip(&mut self, equipment: Equipment) -> Option<Equipment> {
+            self.slot_mut(equipment.slot).replace(equipment)
+        }
+
+        // そうびを外して返す
+        pub fn unequip(&mut self, slot: Slot) -> Option<Equipment> {
+            self.slot_mut(slot).take()
+        }
+
+        // そうびで上がるこうげきりょく
+        pub fn attack(&self) -> f32 {
+            self.iter().map(|equipment| equipment.attack).sum()
+        }
+
+        // そうびで上がるぼうぎょりょく
+        pub fn defence(&self) -> f32 {
+            self.iter().map(|equipment| equipment.defence).sum()
+        }
+
+        // そうびで特殊状態を防げるか
+        pub fn is_immune(&self, status: &SpecialStatusType) -> bool {
+            self.iter()
+                .any(|equipment| equipment.immunities.contains(status))
+        }
+
+        // そうびで使えるスキルの名前
+        pub fn skills(&self) -> impl Iterator<Item = &String> {
+            self.iter().flat_map(|equipment| &equipment.skills)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn equipment(name: &str, slot: Slot, attack: f32, defence: f32) -> Equipment {
+            Equipment {
+                name: name.into(),
+                rarity: Rarity::new(1),
+                slot,
+                attack,
+                defence,
+                immunities: vec![],
+                skills: vec![],
+            }
+        }
+
+        #[test]
+        fn test_equip_replaces_same_slot() {
+            let mut equipped = Equipped::default();
+            assert_eq!(
+                equipped.equip(equipment("どうのつるぎ", Slot::Weapon, 3.0, 0.0)),
+                None
+            );
+            equipped.equip(equipment("かわのよろい", Slot::Armor, 0.0, 2.0));
+            let previous = equipped.equip(equipment("はがねのつるぎ", Slot::Weapon, 6.0, 0.0));
+            assert_eq!(previous.unwrap().name, "どうのつるぎ");
+            assert_eq!(equipped.attack(), 6.0);
+            assert_eq!(equipped.defence(), 2.0);
+
+            // 外すと、その分だけ下がる
+            equipped.unequip(Slot::Armor);
+            assert_eq!(equipped.defence(), 0.0);
+        }
+
+        #[test]
+        fn test_accessory_grants_immunity_and_skills() {
+            let mut amulet = equipment("どくけしのおまもり", Slot::Accessory, 0.0, 1.0);
+            amulet.immunities = vec![SpecialStatusType::Poisoned];
+            amulet.skills = vec!["ヒール".into()];
+            let mut equipped = Equipped::default();
+            assert!(!equipped.is_immune(&SpecialStatusType::Poisoned));
+            equipped.equip(amulet);
+            assert!(equipped.is_immune(&SpecialStatusType::Poisoned));
+            assert!(!equipped.is_immune(&SpecialStatusType::Burned));
+            assert_eq!(equipped.skills().collect::<Vec<_>>(), vec!["ヒール"]);
+        }
+    }
+}
+
+pub use equipment::Equipment;
+pub use equipment::Equipments;
+pub use equipment::Equipped;
+pub use equipment::Slot;
@@ -0,0 +1,198 @@
+// 画面に依存しないゲームの中核。
+// シナリオと戦闘の進行は Game が受け持ち、フロントエンドは Frame を描いてキー操作を送るだけにする。
+// iced のウィンドウとターミナルのどちらでも、同じゲームを遊べる。
+#[allow(clippy::module_inception)]
+mod game_core {
+    use crate::battle_log::LogTone;
+    use crate::battle_rules::Character;
+    use crate::character_card::{self, BadgeKind};
+    use crate::command_menu::{self, MenuFrame, MenuKey};
+    use crate::{battle_ui, Game, Message};
+    use std::time::Instant;
+
+    // 1 回の描画で表示する内容
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Frame {
+        pub info: String, // 画面上部の情報。文字送りで表示済みの部分まで
+        pub enemies: Vec<CardFrame>,
+        pub player: Option<CardFrame>, // 敵と出会ってから表示する
+        pub log: Vec<(String, LogTone)>,
+        pub menu: Option<MenuFrame>,
+        pub error: Option<String>,
+        pub show_next: bool, // つぎへ を表示するか
+    }
+
+    // キャラクターのカードの内容
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct CardFrame {
+        pub name: String,
+        pub level: u8,
+        pub hp: f32,
+        pub hp_max: f32,
+        pub mp: f32,
+        pub mp_max: f32,
+        pub badges: Vec<(String, BadgeKind)>,
+        pub hit: bool, // ダメージを受けた直後か
+    }
+
+    impl Game {
+        // 表示する内容を作る
+        pub fn frame(&self) -> Frame {
+            let result_menu = self.battle_ui.result_menu();
+            let info = match result_menu {
+                Some(_) => self.system_info.clone(),
+                None => self.text_reveal.visible(),
+            };
+            let (enemies, player) = match &self.encountered_enemies {
+                Some(enemies) => {
+                    let enemies = enemies
+                        .characters
+                        .iter()
+                        .map(|enemy| self.card_frame(enemy))
+                        .collect();
+                    (enemies, Some(self.card_frame(&self.player)))
+                }
+                None => (vec![], None),
+            };
+            let menu = match self.battle_ui.current() {
+                Some(battle_ui::Screen::Root(menu)) => Some(menu.frame()),
+                Some(battle_ui::Screen::Skills(menu)) => Some(menu.frame()),
+                Some(battle_ui::Screen::Items(menu)) => Some(menu.frame()),
+                Some(battle_ui::Screen::Target(menu, _)) => Some(menu.frame()),
+                // 行動の結果は文字送りで表示し、つぎへ だけを選べる
+                Some(battle_ui::Screen::Result(_)) | Some(battle_ui::Screen::EnemyTurn(_)) => {
+                    let next = command_menu::CommandItem {
+                        label: "つぎへ".to_string(),
+                        on_select: Some(()),
+                    };
+                    let title = self.text_reveal.visible();
+                    Some(command_menu::frame(&title, vec![next], Some(0)))
+                }
+                // 戦闘の外のメニュー
+                None => self
+                    .area_select
+                    .as_ref()
+                    .map(|menu| menu.frame())
+                    .or_else(|| self.dungeon_menu.as_ref().map(|menu| menu.frame()))
+                    .or_else(|| self.reward_menu.as_ref().map(|menu| menu.frame()))
+                    .or_else(|| self.equip_menu.as_ref().map(|menu| menu.frame())),
+            };
+            Frame {
+                info,
+                enemies,
+                player,
+                log: self.battle_log.toned_lines(),
+                menu,
+                error: self.error.as_ref().map(|error| error.to_string()),
+                show_next: self.show_next_button,
+            }
+        }
+
+        fn card_frame(&self, character: &Character) -> CardFrame {
+            CardFrame {
+                name: character.label().to_string(),
+                level: character.level.value,
+                hp: character.hp,
+                hp_max: character.hp_max,
+                mp: character.mp,
+                mp_max: character.mp_max,
+                badges: character_card::badges(character),
+                hit: self.hit_feedback.progress(character.id).is_some(),
+            }
+        }
+
+        // メニューを操作する。決定はつぎへ や エラーからの立て直しにも使う
+        pub fn press(&mut self, key: MenuKey) {
+            self.update(Message::MenuKey(key));
+        }
+
+        // 文字送りを飛ばす
+        pub fn skip_text(&mut self) {
+            self.update(Message::SkipText);
+        }
+
+        // 時刻を進めて演出を更新する
+        pub fn advance(&mut self, now: Instant) {
+            self.update(Message::Tick(now));
+        }
+
+        // 演出中か。演出中でなければ、時刻を進めなくてよい
+        pub fn is_animating(&self) -> bool {
+            let revealing = !self.text_reveal.is_finished();
+            let waiting_auto_advance = self.auto_advance && self.battle_ui.result_menu().is_some();
+            self.hit_feedback.is_active() || revealing || waiting_auto_advance
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::text_reveal::TextSpeed;
+        use crate::{Game, Message};
+
+        #[test]
+        fn test_frame_follows_key_presses() {
+            let mut game = Game::new().unwrap();
+            game.update(Message::ChangeTextSpeed(TextSpeed::NoWait));
+            // メニューが出るまで、つぎへ を進める
+            let next_menu = |game: &mut Game| {
+                for _ in 0..10 {
+                    if let Some(menu) = game.frame().menu {
+                        return menu;
+                    }
+                    game.press(super::MenuKey::Confirm);
+                }
+                panic!("メニューが　でない");
+            };
+
+            // はじめは町にしか行けない
+            let map = next_menu(&mut game);
+            assert_eq!(map.title, "どこへ　いく？");
+            assert_eq!(map.labels[0], "おしろのまち");
+            assert_eq!(map.labels[1], "？？？");
+            game.press(super::MenuKey::Down);
+            game.press(super::MenuKey::Confirm);
+
+            // 王様からもらったそうびを身につける
+            let equip = next_menu(&mut game);
+            assert_eq!(equip.title, "そうびを　えらぼう");
+            assert_eq!(
+                equip.labels,
+                vec!["　ぶき：どうのつるぎ", "　よろい：かわのよろい", "おわる"]
+            );
+            game.press(super::MenuKey::Down);
+            game.press(super::MenuKey::Confirm);
+            let equip = game.frame().menu.unwrap();
+            assert_eq!(equip.labels[0], "E ぶき：どうのつるぎ");
+            game.press(super::MenuKey::Back);
+            game.press(super::MenuKey::Confirm);
+
+            // 王様の話でフラグが立ち、そうげんに行ける
+            let map = next_menu(&mut game);
+            assert_eq!(map.labels[1], "はじまりのそうげん");
+            game.press(super::MenuKey::Down);
+            game.press(super::MenuKey::Down);
+            game.press(super::MenuKey::Confirm);
+
+            // 敵との遭遇を経て戦闘操作メニューまで進める
+            let menu = next_menu(&mut game);
+            assert_eq!(menu.title, "どうする？");
+            let frame = game.frame();
+            assert!(!frame.enemies.is_empty());
+            assert_eq!(frame.player.as_ref().unwrap().name, "ゆうしゃ");
+            let menu = frame.menu.unwrap();
+            assert_eq!(menu.title, "どうする？");
+            assert_eq!(
+                menu.labels,
+                vec!["スキルをつかう", "アイテムをつかう", "ようすをみる"]
+            );
+            assert_eq!(menu.cursor, None);
+
+            game.press(super::MenuKey::Down);
+            game.press(super::MenuKey::Confirm);
+            assert_eq!(game.frame().menu.unwrap().title, "どの　スキルを　つかう？");
+        }
+    }
+}
+
+pub use game_core::CardFrame;
+pub use game_core::Frame;
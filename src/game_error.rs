@@ -0,0 +1,67 @@
+// ゲームの進行中に起きたエラー。
+// パニックせずに画面上部のバナーで知らせ、プレイヤーが立て直せるようにする。
+#[allow(clippy::module_inception)]
+mod game_error {
+    use crate::battle_ui::InvalidTransition;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum GameError {
+        NoEncounteredEnemies, // 敵と出会っていないのに、敵を狙おうとした
+        NotEnemyCollection,   // 敵との遭遇に、敵でない組み合わせが指定された
+        NoSkillSelected,
+        NoItemSelected,
+        NoTargetSelected,         // 狙う敵が選ばれていないか、もういない
+        UnknownArea(String),      // 遭遇表のないエリア
+        UnknownEnemy(String),     // 遭遇表に、マスタデータにいない敵が書かれている
+        NoSuchArea(String),       // 世界地図にないエリアに入ろうとした
+        AreaLocked(String),       // まだ行けないエリアに入ろうとした
+        UnknownDungeon(String),   // マスタデータにないダンジョンに入ろうとした
+        NoSuchRoom(String),       // いる部屋からつながっていない部屋へ進もうとした
+        UnknownEquipment(String), // マスタデータにない、または持っていないそうび
+        NoDataDir,                // マスタデータを置くディレクトリ(RUSTERN_DIR)が決まっていない
+        MasterData(String),       // マスタデータを読み込めない
+        InvalidTransition(InvalidTransition),
+    }
+
+    impl From<InvalidTransition> for GameError {
+        fn from(error: InvalidTransition) -> Self {
+            GameError::InvalidTransition(error)
+        }
+    }
+
+    impl std::fmt::Display for GameError {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                GameError::NoEncounteredEnemies => write!(f, "てきが　いない。"),
+                GameError::NotEnemyCollection => {
+                    write!(f, "てきでは　ないものが　あらわれようとした。")
+                }
+                GameError::NoSkillSelected => write!(f, "スキルが　えらばれて　いない。"),
+                GameError::NoItemSelected => write!(f, "アイテムが　えらばれて　いない。"),
+                GameError::NoTargetSelected => {
+                    write!(f, "ねらう　てきが　えらばれて　いない。")
+                }
+                GameError::UnknownArea(area) => {
+                    write!(f, "{} には　てきが　あらわれない。", area)
+                }
+                GameError::UnknownEnemy(name) => write!(f, "{} という　てきは　いない。", name),
+                GameError::NoSuchArea(area) => write!(f, "{} という　ばしょは　ない。", area),
+                GameError::AreaLocked(area) => write!(f, "{} には　まだ　いけない。", area),
+                GameError::UnknownDungeon(name) => {
+                    write!(f, "{} という　ダンジョンは　ない。", name)
+                }
+                GameError::NoSuchRoom(room) => write!(f, "{} へは　すすめない。", room),
+                GameError::UnknownEquipment(name) => {
+                    write!(f, "{} という　そうびは　ない。", name)
+                }
+                GameError::NoDataDir => write!(f, "RUSTERN_DIR が　きまって　いない。"),
+                GameError::MasterData(error) => {
+                    write!(f, "マスタデータを　よみこめない：{}", error)
+                }
+                GameError::InvalidTransition(error) => write!(f, "{}", error),
+            }
+        }
+    }
+}
+
+pub use game_error::GameError;
@@ -0,0 +1,174 @@
+// iced のウィンドウ。
+// ゲームの進行は Game に任せ、ウィンドウへの描画とキーボードの受け付けだけを受け持つ。
+#[allow(clippy::module_inception)]
+mod gui {
+    use crate::{battle_ui, character_card, command_menu, game_error, text_reveal, Game, Message};
+    use iced::widget::{checkbox, mouse_area, pick_list, Column, Row, Text};
+    use iced::{Element, Font, Subscription};
+
+    struct App {
+        game: Game,
+    }
+
+    // iced のウィンドウでゲームを遊ぶ。マスタデータを読み込めなければ、ウィンドウを開く前に知らせる
+    pub fn run() -> Result<(), String> {
+        let game = Game::new()
+            .map_err(|error| error.to_string())?
+            .with_autosave();
+        iced::application("Rustern-battle", App::update, App::view)
+            .subscription(App::subscription)
+            .default_font(Font::with_name("ヒラギノ角ゴシック"))
+            .run_with(move || (App { game }, iced::Task::none()))
+            .map_err(|error| error.to_string())
+    }
+
+    impl App {
+        fn update(&mut self, message: Message) {
+            self.game.update(message);
+        }
+
+        // 演出中だけ、画面の描画ごとに時刻を受け取る。文字送り中はキーで飛ばせる
+        fn subscription(&self) -> Subscription<Message> {
+            let mut subscriptions = vec![];
+            if self.game.is_animating() {
+                subscriptions.push(iced::window::frames().map(Message::Tick));
+            }
+            // 矢印キー・Enter・Esc はメニューの操作、スペースは文字送りを飛ばす
+            subscriptions.push(iced::keyboard::on_key_press(|key, _modifiers| {
+                use iced::keyboard::key::{Key, Named};
+                match key {
+                    Key::Named(Named::Space) => Some(Message::SkipText),
+                    _ => command_menu::MenuKey::from_key(&key).map(Message::MenuKey),
+                }
+            }));
+            Subscription::batch(subscriptions)
+        }
+
+        fn view(&self) -> Element<'_, Message> {
+            view(&self.game)
+        }
+    }
+
+    // Game の状態をウィンドウに描く
+    pub(crate) fn view(game: &Game) -> Element<'_, Message> {
+        let mut column = Column::new();
+        // エラーのバナー
+        if let Some(error) = &game.error {
+            column = column.push(error_banner(error));
+        }
+        // 戦闘の結果がなければ、画面上部の情報を文字送りで表示する。クリックで飛ばせる
+        let system_info = match game.battle_ui.result_menu() {
+            Some(_) => Text::new(game.system_info.as_str()),
+            None => Text::new(game.text_reveal.visible()),
+        };
+        column = column.push(mouse_area(system_info).on_press(Message::SkipText));
+        // 敵とプレイヤーのカード
+        if let Some(enemies) = &game.encountered_enemies {
+            let mut enemy_cards = Row::new();
+            for enemy in &enemies.characters {
+                let progress = game.hit_feedback.progress(enemy.id);
+                enemy_cards = enemy_cards.push(character_card::view(enemy, progress));
+            }
+            column = column.push(enemy_cards);
+            let progress = game.hit_feedback.progress(game.player.id);
+            column = column.push(character_card::view(&game.player, progress));
+        }
+        // 戦闘ログ
+        if !game.battle_log.events().is_empty() {
+            column = column.push(game.battle_log.view().map(Message::BattleLog));
+        }
+
+        // サブビューの表示
+        match game.battle_ui.current() {
+            Some(battle_ui::Screen::Root(menu)) => {
+                column = column.push(menu.view().map(Message::BattleOperationMenu));
+            }
+            Some(battle_ui::Screen::Skills(menu)) => {
+                column = column.push(menu.view().map(Message::UseSkillMenu));
+            }
+            Some(battle_ui::Screen::Items(menu)) => {
+                column = column.push(menu.view().map(Message::UseItemMenu));
+            }
+            Some(battle_ui::Screen::Target(menu, _)) => {
+                column = column.push(menu.view().map(Message::TargetEnemyMenu));
+            }
+            Some(battle_ui::Screen::Result(menu)) | Some(battle_ui::Screen::EnemyTurn(menu)) => {
+                let result = menu
+                    .view(game.text_reveal.visible())
+                    .map(Message::BattleResultMenu);
+                column = column.push(mouse_area(result).on_press(Message::SkipText));
+            }
+            None => {}
+        }
+
+        // 世界地図
+        if let Some(menu) = &game.area_select {
+            column = column.push(menu.view().map(Message::AreaSelectMenu));
+        }
+
+        // ダンジョンの探索
+        if let Some(menu) = &game.dungeon_menu {
+            column = column.push(menu.view().map(Message::DungeonMenu));
+        }
+
+        // ふしぎのとうのほうび
+        if let Some(menu) = &game.reward_menu {
+            column = column.push(menu.view().map(Message::RewardMenu));
+        }
+
+        // そうび
+        if let Some(menu) = &game.equip_menu {
+            column = column.push(menu.view().map(Message::EquipMenu));
+        }
+
+        // ゲームの初回でおうさまからアイテムを貰う処理
+        if game.show_items_for_pick {
+            // アイテムリスト
+            let item_candidates = pick_list(
+                game.items_for_get.clone(),
+                game.selected_item.clone(),
+                Message::WaitingSelectItemByUser,
+            );
+            column = column.push(item_candidates);
+        }
+
+        if game.show_next_button {
+            column = column.push(iced::widget::button("つぎへ").on_press(Message::Next));
+        }
+
+        // 文字送りの設定
+        let settings = Row::new()
+            .spacing(8)
+            .push(Text::new("もじの　はやさ"))
+            .push(pick_list(
+                text_reveal::TextSpeed::ALL,
+                Some(game.text_speed),
+                Message::ChangeTextSpeed,
+            ))
+            .push(checkbox("オート", game.auto_advance).on_toggle(Message::ToggleAutoAdvance));
+        column = column.push(settings);
+        column.into()
+    }
+
+    // エラーを赤い帯で表示し、立て直すボタンを添える
+    fn error_banner(error: &game_error::GameError) -> Element<'_, Message> {
+        use iced::widget::{button, container, text};
+        use iced::Color;
+        let row = Row::new()
+            .spacing(8)
+            .push(text!("エラー：{}", error).color(Color::WHITE))
+            .push(button("たてなおす").on_press(Message::RecoverFromError));
+        container(row)
+            .padding(8)
+            .width(iced::Length::Fill)
+            .style(|_theme| container::Style {
+                background: Some(Color::from_rgb(0.7, 0.1, 0.1).into()),
+                ..Default::default()
+            })
+            .into()
+    }
+}
+
+pub use gui::run;
+#[cfg(test)]
+pub(crate) use gui::view;
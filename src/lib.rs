@@ -0,0 +1,2687 @@
+
+mod battle_operation_menu;
+mod target_enemy_menu;
+mod use_skill_menu;
+mod battle_result_menu;
+mod calc_battle_result;
+mod damage_formula;
+mod enemy_ai;
+mod battle_event;
+mod battle_log;
+mod character_card;
+mod text_reveal;
+mod command_menu;
+mod use_item_menu;
+mod battle_ui;
+mod game_error;
+mod game_core;
+mod dice;
+mod encounter_table;
+mod world_map;
+mod area_select_menu;
+mod dungeon;
+mod dungeon_menu;
+mod roguelike;
+mod reward_menu;
+mod save_data;
+mod equipment;
+mod equip_menu;
+mod battle_runner;
+mod simulation;
+mod balance;
+mod tuning;
+mod cli;
+#[cfg(feature = "gui")]
+mod gui;
+
+use crate::battle_rules::Character;
+use crate::damage_formula::DamageFormulaType;
+use crate::RandomCollection::{RandomEnemyCollection, RandomItemCollection};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+pub use crate::battle_log::LogTone;
+pub use crate::character_card::BadgeKind;
+pub use crate::cli::Cli;
+pub use crate::command_menu::{MenuFrame, MenuKey};
+pub use crate::game_core::{CardFrame, Frame};
+#[cfg(feature = "gui")]
+pub use crate::gui::run;
+
+
+
+mod battle_rules {
+    use crate::battle_event::Participant;
+    use crate::equipment::Equipped;
+    use crate::{Level, ParticipantId, Skill, Skills, SpecialStatusType, StatType};
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    // 能力ランクの段階の上限。合計がこれを超えて上がったり下がったりしない
+    pub(crate) const STAT_STAGE_LIMIT: i8 = 6;
+
+    #[derive(Clone, Debug, PartialEq, Deserialize)]
+    pub(crate) struct Character {
+        #[serde(default)]
+        pub(crate) id: ParticipantId, // 戦闘の参加者の番号。戦闘が始まるときに決める
+        pub(crate) name: String,
+        #[serde(default)]
+        pub(crate) label: String, // 画面やログに出す名前。同じ敵が並んだときは A, B... が付く
+        pub(crate) level: Level,
+        pub(crate) hp: f32,
+        pub(crate) hp_max: f32,
+        #[serde(default)]
+        pub(crate) mp: f32,
+        #[serde(default)]
+        pub(crate) mp_max: f32,
+        pub(crate) attack: f32,
+        pub(crate) defence: f32,
+        pub(crate) turn_of_poisoned: usize,
+        pub(crate) turn_of_burned: usize,
+        pub(crate) turn_of_falter: usize,
+        pub(crate) turn_of_blackout: usize,
+        pub(crate) turn_of_frost: usize,
+        pub(crate) turn_of_feather: usize,
+        pub(crate) skills: Skills,
+        #[serde(default)]
+        pub(crate) skill_cooldowns: HashMap<String, usize>, // スキル名ごとの、再使用までの残りターン数
+        #[serde(default)]
+        pub(crate) charging: Option<Charging>, // ため中のスキル
+        #[serde(default)]
+        pub(crate) stat_modifiers: Vec<StatModifier>, // 能力ランクの上昇・下降
+        #[serde(default)]
+        pub(crate) knocked_out: bool, // 倒れているか。倒れると行動も対象選択もできない
+        #[serde(default)]
+        pub(crate) gold: u32, // 敵なら倒したときに落とすゴールド
+        #[serde(default)]
+        pub(crate) equipment: Equipped, // 身につけているそうび
+    }
+
+    // ため中のスキル。残りターン数が 0 になった行動で発動する
+    #[derive(Clone, Debug, PartialEq, Deserialize)]
+    pub(crate) struct Charging {
+        pub(crate) skill: Skill,
+        pub(crate) turns: usize,
+    }
+
+    // 一定ターンの間、能力ランクを上げ下げする効果
+    #[derive(Clone, Debug, PartialEq, Deserialize)]
+    pub(crate) struct StatModifier {
+        pub(crate) stat: StatType,
+        pub(crate) stage: i8, // 段階。正なら上昇、負なら下降
+        pub(crate) turns: usize,
+    }
+
+    // 段階を倍率に変換する。+1 段階で 1.5 倍、-1 段階で 2/3 倍
+    pub(crate) fn stage_rate(stage: i8) -> f32 {
+        if stage >= 0 {
+            (2.0 + stage as f32) / 2.0
+        } else {
+            2.0 / (2.0 - stage as f32)
+        }
+    }
+
+    #[allow(dead_code)]
+    impl Character {
+        // HP を減らし、実際に減った HP を返す。残りの HP を超える分は減らない
+        pub(crate) fn hp_decrease(&mut self, value: f32) -> f32 {
+            let decreased = value.min(self.hp);
+            self.hp -= value;
+            if self.hp <= 0.0 {
+                self.hp = 0.0;
+                self.knocked_out = true;
+            }
+            decreased
+        }
+
+        // 最大 HP を超えない範囲で HP を回復する。倒れているときは回復できず false を返す
+        pub(crate) fn hp_increase(&mut self, value: f32) -> bool {
+            if self.knocked_out {
+                return false;
+            }
+            self.hp += value;
+            if self.hp > self.hp_max {
+                self.hp = self.hp_max;
+            }
+            true
+        }
+
+        pub(crate) fn is_knocked_out(&self) -> bool {
+            self.knocked_out
+        }
+
+        // 画面やログに出す名前。決まっていなければ名前をそのまま使う
+        pub(crate) fn label(&self) -> &str {
+            if self.label.is_empty() {
+                &self.name
+            } else {
+                &self.label
+            }
+        }
+
+        // できごとに記録する参加者
+        pub(crate) fn participant(&self) -> Participant {
+            Participant {
+                id: self.id,
+                name: self.name.clone(),
+                label: self.label().to_string(),
+            }
+        }
+
+        // 倒れた状態から、最大 HP に対する割合の HP で生き返る。倒れていなければ false を返す
+        pub(crate) fn revive(&mut self, rate: f32) -> bool {
+            if !self.knocked_out {
+                return false;
+            }
+            self.knocked_out = false;
+            self.hp = (self.hp_max * rate).max(1.0).min(self.hp_max);
+            true
+        }
+
+        // やどやいこいの場で、HP と MP をすべて回復し、特殊状態や能力ランクを元に戻す
+        pub(crate) fn rest(&mut self) {
+            self.knocked_out = false;
+            self.hp = self.hp_max;
+            self.mp = self.mp_max;
+            self.turn_of_poisoned = 0;
+            self.turn_of_burned = 0;
+            self.turn_of_falter = 0;
+            self.turn_of_blackout = 0;
+            self.turn_of_frost = 0;
+            self.turn_of_feather = 0;
+            self.skill_cooldowns.clear();
+            self.charging = None;
+            self.stat_modifiers.clear();
+        }
+
+        pub(crate) fn hp_set(&mut self, value: f32) {
+            self.hp = value;
+        }
+
+        pub(crate) fn mp_decrease(&mut self, value: f32) {
+            self.mp -= value;
+            if self.mp < 0.0 {
+                self.mp = 0.0;
+            }
+        }
+
+        pub(crate) fn mp_increase(&mut self, value: f32) {
+            self.mp += value;
+            if self.mp > self.mp_max {
+                self.mp = self.mp_max;
+            }
+        }
+
+        // スキルの再使用までの残りターン数
+        pub(crate) fn cooldown_of(&self, skill: &Skill) -> usize {
+            self.skill_cooldowns.get(&skill.name).copied().unwrap_or(0)
+        }
+
+        // スキルを使うのに十分なMPがあり、クールダウン中でないか
+        pub(crate) fn can_use(&self, skill: &Skill) -> bool {
+            self.mp >= skill.mp_cost.value && self.cooldown_of(skill) == 0
+        }
+
+        // テスト用のキャラクター。特殊状態やスキルを持たない
+        #[cfg(test)]
+        pub(crate) fn for_test(level: u8, attack: f32, defence: f32) -> Character {
+            Character {
+                id: Default::default(),
+                name: "テスト".into(),
+                label: String::new(),
+                level: Level::new(level),
+                hp: 10.0,
+                hp_max: 10.0,
+                mp: 0.0,
+                mp_max: 0.0,
+                attack,
+                defence,
+                turn_of_poisoned: 0,
+                turn_of_burned: 0,
+                turn_of_falter: 0,
+                turn_of_blackout: 0,
+                turn_of_frost: 0,
+                turn_of_feather: 0,
+                skills: Skills { skills: vec![] },
+                skill_cooldowns: Default::default(),
+                charging: None,
+                stat_modifiers: vec![],
+                knocked_out: false,
+                gold: 0,
+                equipment: Default::default(),
+            }
+        }
+
+        // かかっている特殊状態と、その残りターン数
+        pub(crate) fn special_statuses(&self) -> Vec<(SpecialStatusType, usize)> {
+            [
+                (SpecialStatusType::Poisoned, self.turn_of_poisoned),
+                (SpecialStatusType::Burned, self.turn_of_burned),
+                (SpecialStatusType::Falter, self.turn_of_falter),
+                (SpecialStatusType::BlackOut, self.turn_of_blackout),
+                (SpecialStatusType::Frozen, self.turn_of_frost),
+                (SpecialStatusType::Feather, self.turn_of_feather),
+            ]
+            .into_iter()
+            .filter(|(_, turns)| *turns > 0)
+            .collect()
+        }
+
+        // 能力ランクの合計段階。上限・下限で切り詰める
+        pub(crate) fn stat_stage(&self, stat: &StatType) -> i8 {
+            let stage: i8 = self
+                .stat_modifiers
+                .iter()
+                .filter(|modifier| &modifier.stat == stat)
+                .map(|modifier| modifier.stage)
+                .sum();
+            stage.clamp(-STAT_STAGE_LIMIT, STAT_STAGE_LIMIT)
+        }
+
+        // 能力ランクによる倍率
+        pub(crate) fn stat_rank(&self, stat: &StatType) -> f32 {
+            stage_rate(self.stat_stage(stat))
+        }
+
+        // 能力ランクを変える。上限・下限を超える分は切り捨て、実際に変わった段階を返す
+        pub(crate) fn add_stat_modifier(&mut self, stat: StatType, stage: i8, turns: usize) -> i8 {
+            let current = self.stat_stage(&stat);
+            let stage = current
+                .saturating_add(stage)
+                .clamp(-STAT_STAGE_LIMIT, STAT_STAGE_LIMIT)
+                - current;
+            if stage != 0 {
+                self.stat_modifiers
+                    .push(StatModifier { stat, stage, turns });
+            }
+            stage
+        }
+
+        // 能力ランクの上昇(または下降)をすべて打ち消す。打ち消した効果があれば true
+        pub(crate) fn dispel_stat_modifiers(&mut self, raised: bool) -> bool {
+            let count = self.stat_modifiers.len();
+            self.stat_modifiers
+                .retain(|modifier| (modifier.stage > 0) != raised);
+            self.stat_modifiers.len() != count
+        }
+
+        // そうびを含めたこうげきりょく
+        fn attack_origin(&self) -> f32 {
+            self.attack + self.equipment.attack()
+        }
+
+        pub(crate) fn attack(&self) -> f32 {
+            let attack = self.attack_origin() * self.stat_rank(&StatType::Attack);
+            // 毒のときは攻撃力が半分になる
+            if self.turn_of_poisoned > 0 {
+                return attack / 2.0;
+            }
+            attack
+        }
+
+        // そうびを含めたぼうぎょりょく
+        fn defence_origin(&self) -> f32 {
+            self.defence + self.equipment.defence()
+        }
+
+        pub(crate) fn defence(&self) -> f32 {
+            self.defence_origin() * self.stat_rank(&StatType::Defence)
+        }
+    }
+}
+
+// 画面に依存しないゲームの状態。iced のウィンドウとターミナルはこれを包んで描画する
+pub struct Game {
+    //サブビュー
+    battle_ui: battle_ui::BattleUi, // 戦闘メニューの状態
+    battle_log: battle_log::BattleLog,
+    //プレイヤー
+    player: Box<Character>,
+    //データ
+    scenario: Vec<Message>,
+    scenario_idx: usize,
+    master_data: MasterData,
+    system_info: String,
+    items_for_get: Vec<Item>,
+    owned_items: Vec<ItemContainer>,
+    selected_item: Option<Item>,
+    usable_skills: Skills,
+    encountered_enemies: Option<Characters>,
+    enemy_turn_queue: Vec<ParticipantId>, // このターンにまだ行動していない敵
+    charge_target: Option<ParticipantId>, // プレイヤーがため中のスキルを当てる敵
+    error: Option<game_error::GameError>, // 立て直すまで表示するエラー
+    story_flags: BTreeSet<String>,        // シナリオで立てたフラグ。エリアを行けるようにする
+    area_select: Option<area_select_menu::AreaSelectMenu>, // 世界地図でエリアを選ぶメニュー
+    exploration: Option<dungeon::Exploration>, // 探索中のダンジョン
+    dungeon_menu: Option<dungeon_menu::DungeonMenu>, // ダンジョンで進む部屋を選ぶメニュー
+    run: Option<roguelike::Run>,          // 挑戦中のふしぎのとう
+    reward_menu: Option<reward_menu::RewardMenu>, // ふしぎのとうのほうびを選ぶメニュー
+    gold: u32,                            // 持っているゴールド
+    save_path: Option<PathBuf>,           // やどやで休んだときに記録を残すファイル
+    owned_equipment: Vec<equipment::Equipment>, // 持っているが、身につけていないそうび
+    equip_menu: Option<equip_menu::EquipMenu>, // そうびを付け外しするメニュー
+    //表示制御
+    show_next_button: bool,
+    show_items_for_pick: bool,
+    hit_feedback: character_card::HitFeedback, // ダメージを受けたカードの演出
+    text_reveal: text_reveal::TextReveal,      // 文字送りの演出
+    //設定
+    text_speed: text_reveal::TextSpeed, // 文字を表示する速さ
+    auto_advance: bool,                 // 戦闘の結果を自動で送るか
+}
+
+// 自動送りで、戦闘の結果をすべて表示してから次に進むまでの時間
+const AUTO_ADVANCE_WAIT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Deserialize)]
+struct Items {
+    items: Vec<Item>,
+}
+
+impl Items {
+    //! 指定したレアリティのアイテムを、指定した数だけ持つ配列を返す
+    fn random_pick(&self, rarity: Rarity, count: usize) -> Vec<Item> {
+        let filtered: Vec<Item> = self
+            .items
+            .iter()
+            .filter(|item| item.rarity == rarity)
+            .cloned()
+            .collect();
+
+        let mut rng = dice::rng();
+        filtered.choose_multiple(&mut rng, count).cloned().collect()
+    }
+}
+
+#[allow(dead_code)]
+struct ItemContainers {
+    containers: Vec<ItemContainer>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ItemContainer {
+    item: Item,
+    amount: usize,
+}
+
+impl std::fmt::Display for ItemContainer {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} (残り: {}個)", self.item.name.clone(), self.amount)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Item {
+    name: String,
+    rarity: Rarity,
+    effect: Effect,
+}
+
+impl Item {
+    // 戦闘では、MP を使わずにすぐ発動するスキルとして使う
+    fn as_skill(&self) -> Skill {
+        Skill {
+            name: self.name.clone(),
+            rarity: self.rarity.clone(),
+            effect: self.effect.clone(),
+            mp_cost: MpCost::default(),
+            cooldown: 0,
+            charge_turns: 0,
+            additional_effects: vec![],
+        }
+    }
+}
+
+impl std::fmt::Display for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Rarity {
+    value: u8,
+}
+
+impl Rarity {
+    #[allow(dead_code)]
+    fn new(value: u8) -> Rarity {
+        Rarity { value }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Level {
+    value: u8,
+}
+
+impl Level {
+    fn new(value: u8) -> Level {
+        Level { value }
+    }
+}
+
+// 戦闘の参加者を見分ける番号。プレイヤーは 0、敵は並んだ順に 1 から
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+struct ParticipantId(u32);
+
+impl ParticipantId {
+    const PLAYER: ParticipantId = ParticipantId(0);
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Skills {
+    skills: Vec<Skill>,
+}
+
+impl Skills {
+    //! 指定したレベルのスキルを、指定した数だけ持つ配列を返す
+    fn random_pick(&self, rarity: Rarity, count: usize) -> Vec<Skill> {
+        let filtered: Vec<Skill> = self
+            .skills
+            .iter()
+            .filter(|skill| skill.rarity == rarity)
+            .cloned()
+            .collect();
+
+        let mut rng = dice::rng();
+        filtered.choose_multiple(&mut rng, count).cloned().collect()
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+struct Skill {
+    name: String,
+    rarity: Rarity,
+    effect: Effect,
+    #[serde(default)]
+    mp_cost: MpCost,
+    #[serde(default)]
+    cooldown: usize, // 使用後、再使用できるまでのターン数
+    #[serde(default)]
+    charge_turns: usize, // 発動までにためるターン数。0 ならすぐに発動する
+    #[serde(default)]
+    additional_effects: Vec<AdditionalEffect>, // 追加効果
+}
+
+// スキルの日本語表現。スキル名とする
+impl std::fmt::Display for Skill {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+enum Effect {
+    Attack(
+        //攻撃
+        Probability, //成功率
+        Power,       //威力
+    ),
+    Heal(
+        //回復
+        Ratio, //最大HPに対する回復割合
+    ),
+    AddSpecialStatusToEnemy(
+        //敵に特殊状態を付与
+        Probability,       //成功率
+        SpecialStatusType, //特殊状態
+    ),
+    AttackAndAddSpecialStatusToEnemy(
+        //敵に攻撃しつつ特殊状態を付与
+        Probability,       //成功率
+        Power,             //威力
+        Probability,       //特殊状態付与確率
+        SpecialStatusType, //特殊状態
+    ),
+    RestoreMp(
+        //MP回復
+        Ratio, //最大MPに対する回復割合
+    ),
+    ChangeStatRankOfSelf(
+        //自分の能力ランクを変える
+        StatType, //能力
+        i8,       //段階(正なら上昇、負なら下降)
+        usize,    //継続ターン数
+    ),
+    ChangeStatRankOfEnemy(
+        //敵の能力ランクを変える
+        Probability, //成功率
+        StatType,    //能力
+        i8,          //段階(正なら上昇、負なら下降)
+        usize,       //継続ターン数
+    ),
+    Revive(
+        //倒れた味方を生き返らせる
+        Ratio, //最大HPに対する回復割合
+    ),
+    DispelSelf, //自分の能力低下を打ち消す
+    DispelEnemy(
+        //敵の能力上昇を打ち消す
+        Probability, //成功率
+    ),
+}
+
+#[allow(dead_code)]
+fn use_skill(skill_type: Effect) {
+    match skill_type {
+        Effect::Attack(_, _) => {}
+        Effect::Heal(_) => {}
+        Effect::AddSpecialStatusToEnemy(_, _) => {}
+        Effect::AttackAndAddSpecialStatusToEnemy(_, _, _, _) => {}
+        Effect::RestoreMp(_) => {}
+        Effect::ChangeStatRankOfSelf(_, _, _) => {}
+        Effect::ChangeStatRankOfEnemy(_, _, _, _) => {}
+        Effect::Revive(_) => {}
+        Effect::DispelSelf => {}
+        Effect::DispelEnemy(_) => {}
+    }
+}
+
+// 力を表す値オブジェクト
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+struct Power {
+    value: f32,
+}
+
+// 発生確率を表す値オブジェクト
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+struct Probability {
+    percentage: u32,
+}
+
+// MPの消費量を表す値オブジェクト
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+struct MpCost {
+    value: f32,
+}
+
+// 割合を表す値オブジェクト
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+struct Ratio {
+    percentage: f32,
+}
+
+// スキルの追加効果。主な効果が成功した後に発動する
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+enum AdditionalEffect {
+    AddSpecialStatus(SpecialStatusType, Probability), // 敵に特殊状態を付与
+    DrainHP(Ratio),                                   // 与えたダメージに対する割合だけ HP を吸収
+    Recoil(Ratio),                                    // 与えたダメージに対する割合だけ反動を受ける
+    DrainMP(Ratio),                                   // 敵の残り MP に対する割合だけ MP を吸収
+}
+
+// 特殊状態の列挙型
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+enum SpecialStatusType {
+    Poisoned, // 継続ダメージ(最大HPの16分の1)、攻撃力ダウン
+    Burned,   // 継続ダメージ(最大HPの8分の1)
+    Falter,   // 回避不能、行動不能、防御力ダウン
+    BlackOut, // 回避不能、攻撃が外れる、追加効果無効
+    Frozen,   // 回避不能、追加効果無効
+    Feather,  // 回避率上昇、防御力ダウン
+}
+
+// 能力ランクで上げ下げする能力の列挙型
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+enum StatType {
+    Attack,  // 攻撃力
+    Defence, // 防御力
+    Speed,   // すばやさ。敵が行動する順番が早くなる
+    Evasion, // 回避率。攻撃が当たりにくくなる
+}
+
+// 特殊状態の日本語表現
+impl std::fmt::Display for SpecialStatusType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SpecialStatusType::Poisoned => write!(f, "毒"),
+            SpecialStatusType::Burned => write!(f, "火傷"),
+            SpecialStatusType::Falter => write!(f, "ひるみ"),
+            SpecialStatusType::BlackOut => write!(f, "暗闇"),
+            SpecialStatusType::Frozen => write!(f, "凍結"),
+            SpecialStatusType::Feather => write!(f, "羽"),
+        }
+    }
+}
+
+impl std::fmt::Display for StatType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StatType::Attack => write!(f, "攻撃力"),
+            StatType::Defence => write!(f, "防御力"),
+            StatType::Speed => write!(f, "すばやさ"),
+            StatType::Evasion => write!(f, "回避率"),
+        }
+    }
+}
+
+// yaml から読み込む想定のデータ
+#[derive(Debug, Clone, Deserialize)]
+struct MasterData {
+    characters: Characters,
+    items: Items,
+    skills: Skills,
+    #[serde(default)]
+    ruleset: Ruleset,
+    #[serde(default)]
+    encounter_tables: encounter_table::EncounterTables, // エリアごとの遭遇表
+    #[serde(default)]
+    world_map: world_map::WorldMap, // 町やダンジョンなどのエリア
+    #[serde(default)]
+    dungeons: dungeon::Dungeons, // 探索するダンジョンの部屋
+    #[serde(default)]
+    roguelike: roguelike::RunRules, // ふしぎのとうの決まり
+    #[serde(default)]
+    equipments: equipment::Equipments, // ぶき・よろい・アクセサリ
+}
+
+// 既定のマスタデータのファイル。RUSTERN_DIR にある example.yml
+fn default_data_path() -> Result<std::path::PathBuf, game_error::GameError> {
+    let dir = std::env::var("RUSTERN_DIR").map_err(|_| game_error::GameError::NoDataDir)?;
+    let file_name = "example.yml";
+    Ok(std::path::PathBuf::from(format!("{}/{}", dir, file_name)))
+}
+
+impl MasterData {
+    // YAML ファイルから読み込む
+    fn load(path: &std::path::Path) -> Result<MasterData, String> {
+        let yaml_contents = std::fs::read_to_string(path)
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+        serde_yaml::from_str(&yaml_contents)
+            .map_err(|error| format!("{}: {}", path.display(), error))
+    }
+}
+
+// 戦闘のルール。省略した項目は既定のルールになる
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Ruleset {
+    #[serde(default)]
+    damage_formula: DamageFormulaType, // ダメージ計算式
+}
+
+// キャラクターの集まり。
+#[derive(Debug, Clone, Deserialize)]
+struct Characters {
+    pub characters: Vec<Character>,
+}
+
+// 敵の集まりの実装。
+impl Characters {
+    //! 指定したレベルの敵を、指定した数だけ持つ配列を返す
+    fn random_pick(&self, level: Level, count: usize) -> Vec<Character> {
+        let filtered: Vec<Character> = self
+            .characters
+            .iter()
+            .filter(|enemy| enemy.level == level)
+            .cloned()
+            .collect();
+
+        let mut rng = dice::rng();
+        filtered.choose_multiple(&mut rng, count).cloned().collect()
+    }
+
+    // 戦闘に出る敵の集まり。番号を振り、同じ名前の敵には A, B... を付けて見分けられるようにする
+    fn for_battle(mut characters: Vec<Character>) -> Characters {
+        let names: Vec<String> = characters.iter().map(|enemy| enemy.name.clone()).collect();
+        let mut suffixes: std::collections::HashMap<String, u8> = Default::default();
+        for (idx, enemy) in characters.iter_mut().enumerate() {
+            enemy.id = ParticipantId(idx as u32 + 1);
+            enemy.label = enemy.name.clone();
+            if names.iter().filter(|name| **name == enemy.name).count() > 1 {
+                let suffix = suffixes.entry(enemy.name.clone()).or_insert(0);
+                enemy.label.push((b'A' + *suffix % 26) as char);
+                *suffix += 1;
+            }
+        }
+        Characters { characters }
+    }
+
+    // 番号の敵が何番目にいるか
+    fn position_of(&self, id: ParticipantId) -> Option<usize> {
+        self.characters.iter().position(|enemy| enemy.id == id)
+    }
+}
+
+// プレイヤーの定義。HPなどを持つ
+#[allow(dead_code)]
+struct Player {
+    hp: f32,
+    skills: Skills,
+    item_containers: Vec<ItemContainer>,
+}
+
+// 敵の日本語表現。同じ敵と見分けられる名前を表示する。
+impl std::fmt::Display for Character {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+// 主処理のメッセージ
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+enum Message {
+    Next,
+    Info(String),
+    UpdateSelectorAndInfo(RandomCollection, String),
+    ShowItemsForPick,
+    HideItemsForPick,
+    WaitingSelectItemByUser(Item),
+    GiveSelectedItemForUser,
+    RandomEncounter(RandomCollection),
+    AreaEncounter(String), // エリアの遭遇表で、出会う敵を決める
+    ShowWorldMap,          // 世界地図を開いて、行き先を選ぶ
+    EnterArea(String),     // エリアに入り、エリアのシナリオを進める
+    SetFlag(String),       // シナリオのフラグを立てて、そのまま次に進む
+    AreaSelectMenu(area_select_menu::Message),
+    EnterDungeon(String), // ダンジョンの探索を始める
+    ShowDungeon,          // 戦闘を終えて、ダンジョンの探索に戻る
+    DungeonMenu(dungeon_menu::Message),
+    StartRun,     // ふしぎのとうに、まっさらなゆうしゃで挑む
+    NextFloor,    // 次の階に上り、敵と戦う
+    OfferRewards, // 戦闘に勝ったほうびを並べる
+    RewardMenu(reward_menu::Message),
+    Rest(u32),             // ゴールドをはらって休み、HP と MP を回復して記録を残す
+    GiveEquipment(String), // そうびを持ち物に加える。既に持っていれば、そのまま次に進む
+    ShowEquipment,         // そうびのメニューを開く
+    EquipMenu(equip_menu::Message),
+    BattleOperationMenu(battle_operation_menu::Message),
+    UseSkillMenu(use_skill_menu::Message),
+    UseItemMenu(use_item_menu::Message),
+    TargetEnemyMenu(target_enemy_menu::Message),
+    BattleResultMenu(battle_result_menu::Message),
+    BattleLog(battle_log::Message),
+    Tick(Instant),                  // 演出のための時刻の更新
+    SkipText,                       // 文字送りを飛ばして、すべて表示する
+    MenuKey(command_menu::MenuKey), // キーボードによるメニューの操作
+    ChangeTextSpeed(text_reveal::TextSpeed),
+    ToggleAutoAdvance(bool),
+    RecoverFromError, // エラーのバナーから立て直す
+}
+
+// 戦闘操作の列挙型
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq)]
+enum BattleOperation {
+    ShowSkills,
+    ShowItemStocks,
+}
+
+// 戦闘操作の日本語を定義
+impl std::fmt::Display for BattleOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BattleOperation::ShowSkills => {
+                write!(f, "スキルをつかう")
+            }
+            BattleOperation::ShowItemStocks => {
+                write!(f, "アイテムをつかう")
+            }
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Clone)]
+enum PageOfBattleOperation {
+    Root,
+    Skills,
+    ItemContainers,
+    Enemies,
+}
+
+// 旅立つときのゆうしゃ。覚えているスキルだけを選べる
+fn new_hero(skills: Skills) -> Box<Character> {
+    Box::new(Character {
+        id: ParticipantId::PLAYER,
+        name: "ゆうしゃ".into(),
+        label: String::new(),
+        level: Level { value: 1 },
+        hp: 100.0,
+        hp_max: 100.0,
+        mp: 20.0,
+        mp_max: 20.0,
+        attack: 5.0,
+        defence: 5.0,
+        turn_of_poisoned: 0,
+        turn_of_burned: 0,
+        turn_of_falter: 0,
+        turn_of_blackout: 0,
+        turn_of_frost: 0,
+        turn_of_feather: 0,
+        skills,
+        skill_cooldowns: Default::default(),
+        charging: None,
+        stat_modifiers: vec![],
+        knocked_out: false,
+        gold: 0,
+        equipment: Default::default(),
+    })
+}
+
+impl Game {
+    // ゲーム開始時の処理
+    pub fn new() -> Result<Self, game_error::GameError> {
+        // マスタデータ(YAML)読み込み
+        let master_data =
+            MasterData::load(&default_data_path()?).map_err(game_error::GameError::MasterData)?;
+        Ok(Game::with_master_data(master_data))
+    }
+
+    // 読み込み済みのマスタデータでゲームを始める
+    fn with_master_data(master_data: MasterData) -> Self {
+        // 初期化
+        let first_message: String =
+            "おうさま：おお　ゆうしゃよ　まおうを　たおしに　ゆくのじゃ".into();
+        let usable_skills = Skills {
+            skills: master_data.skills.random_pick(Rarity { value: 1 }, 2),
+        };
+
+        Self {
+            player: new_hero(usable_skills.clone()),
+            battle_ui: Default::default(),
+            battle_log: battle_log::BattleLog::new(),
+            //データ
+            scenario: vec![
+                // Message::Info(first_message.clone()),
+                // Message::Info("おうさま：アイテムを　ひとつ　さずけよう。".into()),
+                // Message::UpdateSelectorAndInfo(RandomItemCollection(Rarity::new(1), 2), "どの　アイテムを　もらう？".into()),
+                // Message::ShowItemsForPick,
+                // Message::GiveSelectedItemForUser,
+                Message::Info("さあ　まおうを　たおす　たびの　はじまりだ。".into()),
+                Message::ShowWorldMap,
+            ],
+            scenario_idx: 0,
+            master_data,
+            system_info: first_message.clone(),
+            items_for_get: vec![],
+            owned_items: vec![],
+            selected_item: None,
+            encountered_enemies: None,
+            enemy_turn_queue: vec![],
+            charge_target: None,
+            usable_skills,
+            error: None,
+            story_flags: BTreeSet::new(),
+            area_select: None,
+            exploration: None,
+            dungeon_menu: None,
+            run: None,
+            reward_menu: None,
+            gold: 20,
+            save_path: None,
+            owned_equipment: vec![],
+            equip_menu: None,
+            //表示制御
+            show_next_button: true,
+            show_items_for_pick: false,
+            hit_feedback: Default::default(),
+            text_reveal: Default::default(),
+            text_speed: Default::default(),
+            auto_advance: false,
+        }
+    }
+
+    // 画面を遷移する。許されていない遷移なら、画面はそのままにしてエラーを返す
+    fn transition(&mut self, screen: battle_ui::Screen) -> Result<(), game_error::GameError> {
+        Ok(self.battle_ui.go(screen)?)
+    }
+
+    // プレイヤーの MP やクールダウンに応じたスキル選択メニューを作る
+    fn new_use_skill_menu(&self) -> use_skill_menu::UseSkillMenu {
+        use_skill_menu::UseSkillMenu::new(self.battle_skills(), *self.player.clone())
+    }
+
+    // 戦闘で使えるスキル。覚えているスキルに、そうびで使えるスキルを加える
+    fn battle_skills(&self) -> Skills {
+        let mut skills = self.usable_skills.clone();
+        for name in self.player.equipment.skills() {
+            let skill = self
+                .master_data
+                .skills
+                .skills
+                .iter()
+                .find(|s| &s.name == name);
+            if let Some(skill) = skill.filter(|skill| !skills.skills.contains(skill)) {
+                skills.skills.push(skill.clone());
+            }
+        }
+        skills
+    }
+
+    // プレイヤーと敵の HP。ダメージの演出に使う
+    fn hp_snapshot(&self) -> Vec<(ParticipantId, f32)> {
+        let mut snapshot = vec![(self.player.id, self.player.hp)];
+        if let Some(enemies) = &self.encountered_enemies {
+            for enemy in &enemies.characters {
+                snapshot.push((enemy.id, enemy.hp));
+            }
+        }
+        snapshot
+    }
+
+    // 行動の前より HP が減ったカードの演出を始める
+    fn start_hit_feedback(&mut self, before: &[(ParticipantId, f32)]) {
+        let now = Instant::now();
+        for ((id, hp_before), (_, hp_after)) in before.iter().zip(self.hp_snapshot()) {
+            if hp_after < *hp_before {
+                self.hit_feedback.start(*id, now);
+            }
+        }
+    }
+
+    // プレイヤーの行動。アイテムは使うと 1 つ減る
+    fn player_action(
+        &mut self,
+        action: battle_ui::Action,
+        enemy_id: Option<ParticipantId>,
+    ) -> Result<(), game_error::GameError> {
+        match action {
+            battle_ui::Action::Skill(skill) => self.player_turn(skill, enemy_id),
+            battle_ui::Action::Item(item) => {
+                self.player_turn(item.as_skill(), enemy_id)?;
+                if let Some(idx) = self
+                    .owned_items
+                    .iter()
+                    .position(|container| container.item == item)
+                {
+                    self.owned_items[idx].amount -= 1;
+                    if self.owned_items[idx].amount == 0 {
+                        self.owned_items.remove(idx);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // プレイヤーのターン。スキルを敵に使い、その後は生き残っている敵が順番に行動する
+    fn player_turn(
+        &mut self,
+        skill: Skill,
+        enemy_id: Option<ParticipantId>,
+    ) -> Result<(), game_error::GameError> {
+        let hp_before = self.hp_snapshot();
+        let enemies = self
+            .encountered_enemies
+            .as_ref()
+            .ok_or(game_error::GameError::NoEncounteredEnemies)?;
+        let target_idx = enemy_id
+            .and_then(|id| enemies.position_of(id))
+            .ok_or(game_error::GameError::NoTargetSelected)?;
+        // 狙った敵が倒れていたら、生き残っている別の敵を狙う
+        let target_idx = if enemies.characters[target_idx].is_knocked_out() {
+            enemies
+                .characters
+                .iter()
+                .position(|enemy| !enemy.is_knocked_out())
+                .ok_or(game_error::GameError::NoTargetSelected)?
+        } else {
+            target_idx
+        };
+        let enemy = enemies.characters[target_idx].clone();
+        if skill.charge_turns > 0 && self.player.charging.is_none() {
+            // ためたスキルは、実際に狙った敵に向けて発動する
+            self.charge_target = Some(enemy.id);
+        }
+        // ルールで指定されたダメージ計算式
+        let damage_formula = self.master_data.ruleset.damage_formula.formula();
+        let menu = battle_result_menu::BattleResultMenu::new(
+            skill,
+            &mut self.player,
+            enemy,
+            damage_formula.as_ref(),
+        );
+        // 戦闘結果をプレイヤーと敵に反映する
+        *self.player = menu.from();
+        if let Some(enemies) = &mut self.encountered_enemies {
+            enemies.characters[target_idx] = menu.to();
+        }
+        self.finish_player_turn(menu, &hp_before)
+    }
+
+    // プレイヤーは様子を見る。MP が尽きていても、ターンを進められる
+    fn player_wait(&mut self) -> Result<(), game_error::GameError> {
+        let hp_before = self.hp_snapshot();
+        let enemy = self
+            .encountered_enemies
+            .as_ref()
+            .and_then(|enemies| {
+                let mut living = enemies.characters.iter();
+                living.find(|enemy| !enemy.is_knocked_out()).cloned()
+            })
+            .ok_or(game_error::GameError::NoEncounteredEnemies)?;
+        let menu = battle_result_menu::BattleResultMenu::waiting(&self.player, enemy);
+        self.finish_player_turn(menu, &hp_before)
+    }
+
+    // プレイヤーの行動の結果を表示し、生き残っている敵の行動を順番に並べる
+    fn finish_player_turn(
+        &mut self,
+        menu: battle_result_menu::BattleResultMenu,
+        hp_before: &[(ParticipantId, f32)],
+    ) -> Result<(), game_error::GameError> {
+        self.start_hit_feedback(hp_before);
+        self.battle_log.push(menu.events());
+        let result = battle_ui::Screen::Result(menu);
+        // 生き残っている敵が順番に行動する
+        self.enemy_turn_queue = match &self.encountered_enemies {
+            None => vec![],
+            Some(enemies) => {
+                let mut queue: Vec<&Character> = enemies
+                    .characters
+                    .iter()
+                    .filter(|enemy| !enemy.is_knocked_out())
+                    .collect();
+                // すばやさのランクが高い敵から行動する
+                queue.sort_by(|a, b| {
+                    let speed_of = |enemy: &&Character| enemy.stat_stage(&StatType::Speed);
+                    speed_of(b).cmp(&speed_of(a))
+                });
+                queue.iter().map(|enemy| enemy.id).collect()
+            }
+        };
+        self.transition(result)
+    }
+
+    // 敵のターン。AI が選んだスキルでプレイヤーを攻撃する
+    fn enemy_turn(&mut self, enemy_id: ParticipantId) -> Result<(), game_error::GameError> {
+        let hp_before = self.hp_snapshot();
+        let Some(enemies) = &mut self.encountered_enemies else {
+            return Err(game_error::GameError::NoEncounteredEnemies);
+        };
+        let Some(enemy_idx) = enemies.position_of(enemy_id) else {
+            return Ok(());
+        };
+        let mut enemy = enemies.characters[enemy_idx].clone();
+        if enemy.is_knocked_out() {
+            return Ok(());
+        }
+        // 倒れた味方がいれば、先に生き返らせる
+        if let Some((skill, ally_idx)) = enemy_ai::choose_revive(&enemy, &enemies.characters) {
+            let damage_formula = self.master_data.ruleset.damage_formula.formula();
+            let menu = battle_result_menu::BattleResultMenu::new(
+                skill,
+                &mut enemy,
+                enemies.characters[ally_idx].clone(),
+                damage_formula.as_ref(),
+            );
+            enemies.characters[enemy_idx] = menu.from();
+            enemies.characters[ally_idx] = menu.to();
+            self.start_hit_feedback(&hp_before);
+            self.battle_log.push(menu.events());
+            return self.transition(battle_ui::Screen::EnemyTurn(menu));
+        }
+        let menu = match enemy_ai::choose_skill(&enemy) {
+            Some(skill) => {
+                let damage_formula = self.master_data.ruleset.damage_formula.formula();
+                battle_result_menu::BattleResultMenu::new(
+                    skill,
+                    &mut enemy,
+                    *self.player.clone(),
+                    damage_formula.as_ref(),
+                )
+            }
+            // MP が足りず使えるスキルがない
+            None => battle_result_menu::BattleResultMenu::waiting(&enemy, *self.player.clone()),
+        };
+        // 戦闘結果を敵とプレイヤーに反映する
+        enemies.characters[enemy_idx] = menu.from();
+        *self.player = menu.to();
+        self.start_hit_feedback(&hp_before);
+        self.battle_log.push(menu.events());
+        self.transition(battle_ui::Screen::EnemyTurn(menu))
+    }
+
+    fn hide_all_components(&mut self) {
+        self.show_next_button = false;
+        self.show_items_for_pick = false;
+    }
+    fn update(&mut self, message: Message) {
+        // ログの書き出しや演出では、画面の表示を変えない
+        match message {
+            Message::BattleLog(message) => return self.update_battle_log(message),
+            Message::Tick(now) => return self.tick(now),
+            Message::SkipText => return self.text_reveal.skip(),
+            Message::MenuKey(key) => return self.on_menu_key(key),
+            Message::ChangeTextSpeed(speed) => {
+                self.text_speed = speed;
+                return;
+            }
+            Message::ToggleAutoAdvance(auto_advance) => {
+                self.auto_advance = auto_advance;
+                return;
+            }
+            _ => {}
+        }
+        self.hide_all_components();
+        if let Err(error) = self.update_scenario(message) {
+            // 画面はそのままにして、エラーをバナーで知らせる
+            self.error = Some(error);
+        }
+        // 変わった文章を、文字送りで表示しはじめる
+        self.text_reveal.show(self.message_text(), self.text_speed);
+    }
+
+    // シナリオとメニューの操作を進める。進められなければエラーを返す
+    fn update_scenario(&mut self, message: Message) -> Result<(), game_error::GameError> {
+        match message {
+            Message::Next => {
+                // シナリオを進める
+                self.scenario_idx += 1;
+                if let Some(msg) = self.scenario.get(self.scenario_idx) {
+                    self.update(msg.clone());
+                }
+            }
+            Message::ShowItemsForPick => {
+                // アイテムを貰うためのドロップダウンリストを表示する
+                self.show_items_for_pick = true;
+            }
+            Message::HideItemsForPick => {
+                // アイテムを貰うためのドロップダウンリストを隠す
+                self.show_items_for_pick = false;
+            }
+            Message::Info(info) => {
+                // 画面上部に情報を表示したうえで、次に進むボタンを表示する
+                self.system_info = info;
+                self.show_next_button = true;
+            }
+            Message::UpdateSelectorAndInfo(random_collection, info) => {
+                // ドロップダウンリストの内容を変更しつつ、画面上部に情報を表示する
+                match random_collection {
+                    RandomItemCollection(rarity, count) => {
+                        self.items_for_get =
+                            self.master_data.items.random_pick(rarity, count as usize);
+                    }
+                    RandomEnemyCollection(level, count) => {
+                        self.encountered_enemies = Some(Characters::for_battle(
+                            self.master_data
+                                .characters
+                                .random_pick(level, count as usize),
+                        ));
+                    }
+                };
+                self.system_info = info;
+                self.update(Message::Next);
+            }
+            Message::WaitingSelectItemByUser(item) => {
+                // ユーザーがドロップダウンリストから選ぶのを待つ
+                self.selected_item = Some(item);
+                self.show_items_for_pick = true;
+                if self.selected_item.is_some() {
+                    self.show_next_button = true;
+                }
+            }
+            Message::GiveSelectedItemForUser => {
+                // 選択されたアイテムを得る
+                if let Some(selected_item) = self.selected_item.clone() {
+                    self.give_item(selected_item.clone());
+                    self.system_info = format!("{}　を　てにいれた！", selected_item.name);
+                    self.selected_item = None;
+                    self.items_for_get = vec![];
+                } else {
+                    self.system_info = "アイテムが　えらばれて　いない。".to_string();
+                }
+                self.show_next_button = true;
+            }
+            Message::RandomEncounter(random_enemy_collection) => {
+                match random_enemy_collection {
+                    RandomEnemyCollection(level, count) => {
+                        self.system_info = "てきが　あらわれた！".to_string();
+                        let characters = self
+                            .master_data
+                            .characters
+                            .random_pick(level, count as usize);
+                        self.encountered_enemies = Some(Characters::for_battle(characters));
+                        self.show_next_button = true;
+                    }
+
+                    _ => return Err(game_error::GameError::NotEnemyCollection),
+                }
+                self.battle_log.clear();
+            }
+            Message::AreaEncounter(area) => {
+                let characters = self
+                    .master_data
+                    .encounter_tables
+                    .roll(&area, &self.master_data.characters)?;
+                self.system_info = "てきが　あらわれた！".to_string();
+                self.encountered_enemies = Some(Characters::for_battle(characters));
+                self.show_next_button = true;
+                self.battle_log.clear();
+            }
+            Message::ShowWorldMap => {
+                // 戦闘や探索を終えて、行けるエリアを並べる
+                self.encountered_enemies = None;
+                self.exploration = None;
+                self.dungeon_menu = None;
+                self.battle_log.clear();
+                self.system_info = "せかいちず".to_string();
+                self.area_select = Some(area_select_menu::AreaSelectMenu::new(
+                    &self.master_data.world_map,
+                    &self.story_flags,
+                ));
+            }
+            Message::EnterArea(name) => {
+                let area = self
+                    .master_data
+                    .world_map
+                    .find(&name)
+                    .ok_or_else(|| game_error::GameError::NoSuchArea(name.clone()))?;
+                if !area.is_unlocked(&self.story_flags) {
+                    return Err(game_error::GameError::AreaLocked(name));
+                }
+                // エリアのシナリオを、はじめから進める
+                self.area_select = None;
+                self.system_info = area.arrival();
+                self.scenario = area.scenario();
+                self.scenario_idx = 0;
+                if let Some(msg) = self.scenario.first() {
+                    self.update(msg.clone());
+                }
+            }
+            Message::SetFlag(flag) => {
+                self.story_flags.insert(flag);
+                self.update(Message::Next);
+            }
+            Message::AreaSelectMenu(message) => {
+                // エリア選択メニュー
+                let Some(menu) = &mut self.area_select else {
+                    return Ok(());
+                };
+                menu.update(message.clone());
+                if let area_select_menu::Message::OnClickNext = message {
+                    if let Some(area) = menu.area.clone() {
+                        self.update(Message::EnterArea(area));
+                    }
+                }
+            }
+            Message::EnterDungeon(name) => {
+                let dungeon = self
+                    .master_data
+                    .dungeons
+                    .find(&name)
+                    .ok_or_else(|| game_error::GameError::UnknownDungeon(name.clone()))?;
+                self.exploration = Some(dungeon::Exploration::new(dungeon.clone()));
+                self.update(Message::ShowDungeon);
+            }
+            Message::ShowDungeon => {
+                // 探索中の部屋で、進む先を選ぶ
+                let Some(exploration) = &self.exploration else {
+                    return Ok(());
+                };
+                self.encountered_enemies = None;
+                self.battle_log.clear();
+                self.system_info = exploration.describe();
+                self.dungeon_menu = Some(dungeon_menu::DungeonMenu::new(exploration));
+            }
+            Message::DungeonMenu(message) => {
+                // ダンジョンの探索メニュー
+                let Some(menu) = &mut self.dungeon_menu else {
+                    return Ok(());
+                };
+                menu.update(message.clone());
+                if let dungeon_menu::Message::OnClickNext = message {
+                    if let Some(command) = menu.command.clone() {
+                        self.explore(command)?;
+                    }
+                }
+            }
+            Message::StartRun => {
+                // 旅のゆうしゃをしまい、まっさらなゆうしゃで挑む
+                let usable_skills = Skills {
+                    skills: self.master_data.skills.random_pick(Rarity::new(1), 2),
+                };
+                let stash = roguelike::Stash {
+                    player: std::mem::replace(&mut self.player, new_hero(usable_skills.clone())),
+                    owned_items: std::mem::take(&mut self.owned_items),
+                    usable_skills: std::mem::replace(&mut self.usable_skills, usable_skills),
+                    gold: self.gold,
+                };
+                let rules = self.master_data.roguelike.clone();
+                self.run = Some(roguelike::Run::new(rules, stash));
+                self.update(Message::NextFloor);
+            }
+            Message::NextFloor => {
+                let Some(run) = &mut self.run else {
+                    return Ok(());
+                };
+                run.floor += 1;
+                if run.floor > run.rules.floors {
+                    return self.finish_run(true);
+                }
+                let floor = run.floor;
+                let enemies = run.enemies(&self.master_data.characters)?;
+                // 勝ったら、ほうびを選ぶ
+                self.start_battle_then(enemies, vec![Message::OfferRewards]);
+                self.system_info = format!("{}かい　てきが　あらわれた！", floor);
+            }
+            Message::OfferRewards => {
+                // 倒れて挑戦が終わっていれば、とばして次に進む
+                let Some(run) = &mut self.run else {
+                    self.update(Message::Next);
+                    return Ok(());
+                };
+                if let Some(enemies) = self.encountered_enemies.take() {
+                    run.defeated += enemies.characters.len();
+                }
+                self.battle_log.clear();
+                let rewards = run.offer(&self.master_data.skills, &self.master_data.items);
+                if rewards.is_empty() {
+                    self.update(Message::NextFloor);
+                } else {
+                    self.system_info = "ほうびを　ひとつ　えらぼう。".to_string();
+                    self.reward_menu = Some(reward_menu::RewardMenu::new(rewards));
+                }
+            }
+            Message::RewardMenu(message) => {
+                // ほうびの選択メニュー
+                let Some(menu) = &mut self.reward_menu else {
+                    return Ok(());
+                };
+                menu.update(message.clone());
+                if let reward_menu::Message::OnClickNext = message {
+                    if let Some(reward) = menu.selected().cloned() {
+                        self.reward_menu = None;
+                        self.take_reward(reward);
+                        self.update(Message::NextFloor);
+                    }
+                }
+            }
+            Message::Rest(cost) => {
+                // やどややいこいの場で休む
+                if self.gold < cost {
+                    self.system_info = "ゴールドが　たりない。".to_string();
+                    self.show_next_button = true;
+                    return Ok(());
+                }
+                self.gold -= cost;
+                self.player.rest();
+                let mut info = match cost {
+                    0 => "ひとやすみ　した。".to_string(),
+                    cost => format!("{}ゴールドを　はらって　やどに　とまった。", cost),
+                };
+                info.push_str("\nHP と MP が　かいふくした。");
+                match self.autosave() {
+                    Some(Ok(())) => info.push_str("\nぼうけんを　きろく　した。"),
+                    Some(Err(error)) => {
+                        info.push_str(&format!("\nきろくに　しっぱいした。{}", error))
+                    }
+                    None => {}
+                }
+                self.system_info = info;
+                self.show_next_button = true;
+            }
+            Message::GiveEquipment(name) => {
+                let owned = self
+                    .player
+                    .equipment
+                    .iter()
+                    .chain(&self.owned_equipment)
+                    .any(|equipment| equipment.name == name);
+                if owned {
+                    self.update(Message::Next);
+                    return Ok(());
+                }
+                let equipment = self
+                    .master_data
+                    .equipments
+                    .find(&name)
+                    .ok_or_else(|| game_error::GameError::UnknownEquipment(name.clone()))?;
+                self.owned_equipment.push(equipment.clone());
+                self.system_info = format!("{}を　てにいれた。", name);
+                self.show_next_button = true;
+            }
+            Message::ShowEquipment => {
+                self.show_next_button = false;
+                self.open_equip_menu();
+            }
+            Message::EquipMenu(message) => {
+                // そうびのメニュー
+                let Some(menu) = &mut self.equip_menu else {
+                    return Ok(());
+                };
+                menu.update(message.clone());
+                if let equip_menu::Message::OnClickNext = message {
+                    if let Some(command) = menu.command.clone() {
+                        self.change_equipment(command)?;
+                    }
+                }
+            }
+            Message::BattleOperationMenu(message) => {
+                // 戦闘操作メニュー
+                match message {
+                    battle_operation_menu::Message::Initial if self.battle_ui.kind().is_none() => {
+                        // 戦闘操作メニューを表示する
+                        let menu = battle_operation_menu::BattleOperationMenu::new();
+                        self.transition(battle_ui::Screen::Root(menu))?;
+                    }
+                    battle_operation_menu::Message::OnClickNext => {
+                        // 選んだ操作に応じて、スキルかアイテムの選択へ進む
+                        if let Some(battle_ui::Screen::Root(menu)) = self.battle_ui.current() {
+                            match menu.operation {
+                                Some(battle_operation_menu::Operation::ShowSkills) => {
+                                    let menu = self.new_use_skill_menu();
+                                    self.transition(battle_ui::Screen::Skills(menu))?;
+                                }
+                                Some(battle_operation_menu::Operation::ShowItemContainers) => {
+                                    let menu =
+                                        use_item_menu::UseItemMenu::new(self.owned_items.clone());
+                                    self.transition(battle_ui::Screen::Items(menu))?;
+                                }
+                                Some(battle_operation_menu::Operation::Wait) => {
+                                    self.player_wait()?
+                                }
+                                None => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                if let Some(battle_ui::Screen::Root(menu)) = self.battle_ui.current_mut() {
+                    menu.update(message);
+                }
+            }
+            Message::UseSkillMenu(message) => {
+                // スキル選択メニュー
+                match &message {
+                    use_skill_menu::Message::OnClickNext => {
+                        // 選んだスキルを使う敵の選択へ進む
+                        if let Some(battle_ui::Screen::Skills(menu)) = self.battle_ui.current() {
+                            let skill = menu
+                                .skill
+                                .clone()
+                                .ok_or(game_error::GameError::NoSkillSelected)?;
+                            self.open_target_enemy_menu(battle_ui::Action::Skill(skill))?;
+                        }
+                    }
+                    use_skill_menu::Message::OnClickBack => {
+                        // 戦闘操作メニューに戻る
+                        self.battle_ui.back();
+                    }
+                    _ => {}
+                }
+                if let Some(battle_ui::Screen::Skills(menu)) = self.battle_ui.current_mut() {
+                    menu.update(message);
+                }
+            }
+            Message::UseItemMenu(message) => {
+                // アイテム選択メニュー
+                match &message {
+                    use_item_menu::Message::OnClickNext => {
+                        // 選んだアイテムを使う敵の選択へ進む
+                        if let Some(battle_ui::Screen::Items(menu)) = self.battle_ui.current() {
+                            let item = menu
+                                .item
+                                .clone()
+                                .ok_or(game_error::GameError::NoItemSelected)?;
+                            self.open_target_enemy_menu(battle_ui::Action::Item(item))?;
+                        }
+                    }
+                    use_item_menu::Message::OnClickBack => {
+                        // 戦闘操作メニューに戻る
+                        self.battle_ui.back();
+                    }
+                    _ => {}
+                }
+                if let Some(battle_ui::Screen::Items(menu)) = self.battle_ui.current_mut() {
+                    menu.update(message);
+                }
+            }
+            Message::TargetEnemyMenu(message) => {
+                match &message {
+                    target_enemy_menu::Message::OnClickNext => {
+                        // 選んだスキルかアイテムを、選んだ敵に使う
+                        if let Some(battle_ui::Screen::Target(menu, action)) =
+                            self.battle_ui.current()
+                        {
+                            let action = action.clone();
+                            let enemy_id = menu.enemy;
+                            self.player_action(action, enemy_id)?;
+                        }
+                    }
+                    target_enemy_menu::Message::OnClickBack => {
+                        // スキルかアイテムの選択に戻る
+                        self.battle_ui.back();
+                    }
+                    _ => {}
+                }
+                if let Some(battle_ui::Screen::Target(menu, _)) = self.battle_ui.current_mut() {
+                    menu.update(message);
+                }
+            }
+            Message::BattleResultMenu(message) => {
+                if let Some(battle_ui::Screen::Result(menu) | battle_ui::Screen::EnemyTurn(menu)) =
+                    self.battle_ui.current_mut()
+                {
+                    menu.update(message.clone());
+                }
+                match message {
+                    battle_result_menu::Message::Initial => {}
+                    battle_result_menu::Message::OnClickNext
+                        if self.battle_ui.result_menu().is_some() =>
+                    {
+                        // 倒れた敵は行動しない
+                        if let Some(enemies) = &self.encountered_enemies {
+                            self.enemy_turn_queue.retain(|id| {
+                                enemies
+                                    .position_of(*id)
+                                    .is_some_and(|idx| !enemies.characters[idx].is_knocked_out())
+                            });
+                        }
+                        let all_enemies_knocked_out =
+                            self.encountered_enemies.as_ref().is_some_and(|enemies| {
+                                enemies
+                                    .characters
+                                    .iter()
+                                    .all(|enemy| enemy.is_knocked_out())
+                            });
+                        if self.player.is_knocked_out() && self.run.is_some() {
+                            // ふしぎのとうでは、倒れたら挑戦が終わる
+                            self.enemy_turn_queue = vec![];
+                            self.battle_ui.close();
+                            self.battle_log.can_export = true;
+                            self.finish_run(false)?;
+                        } else if self.player.is_knocked_out() {
+                            // プレイヤーが倒れたら、以降の行動はしない
+                            self.enemy_turn_queue = vec![];
+                            self.battle_ui.close();
+                            self.system_info = "ゲームオーバー".to_string();
+                            self.battle_log.can_export = true;
+                        } else if all_enemies_knocked_out {
+                            // 敵が全員倒れたら、戦闘に勝利してシナリオを進める
+                            self.enemy_turn_queue = vec![];
+                            self.battle_ui.close();
+                            self.system_info = "てきを　ぜんぶ　たおした！".to_string();
+                            // 倒した敵が落としたゴールドを拾う
+                            let gold = self
+                                .encountered_enemies
+                                .iter()
+                                .flat_map(|enemies| &enemies.characters)
+                                .map(|enemy| enemy.gold)
+                                .sum::<u32>();
+                            if gold > 0 {
+                                self.gold += gold;
+                                self.system_info
+                                    .push_str(&format!("\n{}ゴールドを　てにいれた。", gold));
+                            }
+                            self.battle_log.can_export = true;
+                            self.show_next_button = true;
+                        } else if !self.enemy_turn_queue.is_empty() {
+                            // 次の敵が行動する
+                            let enemy_id = self.enemy_turn_queue.remove(0);
+                            self.enemy_turn(enemy_id)?;
+                        } else if let Some(charging) = self.player.charging.clone() {
+                            // ため中のスキルは、メニューを出さずに続ける
+                            self.player_turn(charging.skill, self.charge_target)?;
+                        } else {
+                            // 次のターン
+                            let menu = battle_operation_menu::BattleOperationMenu::new();
+                            self.transition(battle_ui::Screen::Root(menu))?;
+                        }
+                    }
+                    battle_result_menu::Message::OnClickNext => {}
+                }
+            }
+            Message::RecoverFromError => self.recover_from_error()?,
+            Message::BattleLog(_)
+            | Message::Tick(_)
+            | Message::SkipText
+            | Message::MenuKey(_)
+            | Message::ChangeTextSpeed(_)
+            | Message::ToggleAutoAdvance(_) => {}
+        }
+        Ok(())
+    }
+
+    // アイテムを 1 つ持ち物に加える
+    fn give_item(&mut self, item: Item) {
+        if let Some(existing_item) = self
+            .owned_items
+            .iter_mut()
+            .find(|container| container.item == item)
+        {
+            existing_item.amount += 1;
+        } else {
+            self.owned_items.push(ItemContainer { item, amount: 1 });
+        }
+    }
+
+    // そうびのメニューを開き、いまのこうげきりょくとぼうぎょりょくを表示する
+    fn open_equip_menu(&mut self) {
+        self.equip_menu = Some(equip_menu::EquipMenu::new(
+            &self.player.equipment,
+            &self.owned_equipment,
+        ));
+        self.system_info = format!(
+            "こうげき　{}　／　ぼうぎょ　{}",
+            self.player.attack(),
+            self.player.defence()
+        );
+    }
+
+    // 選んだそうびを付け外しする。終えたらシナリオを進める
+    fn change_equipment(
+        &mut self,
+        command: equip_menu::Command,
+    ) -> Result<(), game_error::GameError> {
+        match command {
+            equip_menu::Command::Unequip(slot) => {
+                if let Some(equipment) = self.player.equipment.unequip(slot) {
+                    self.owned_equipment.push(equipment);
+                }
+            }
+            equip_menu::Command::Equip(name) => {
+                let idx = self
+                    .owned_equipment
+                    .iter()
+                    .position(|equipment| equipment.name == name)
+                    .ok_or(game_error::GameError::UnknownEquipment(name))?;
+                let equipment = self.owned_equipment.remove(idx);
+                if let Some(previous) = self.player.equipment.equip(equipment) {
+                    self.owned_equipment.push(previous);
+                }
+            }
+            equip_menu::Command::Done => {
+                self.equip_menu = None;
+                self.update(Message::Next);
+                return Ok(());
+            }
+        }
+        self.open_equip_menu();
+        Ok(())
+    }
+
+    // ダンジョンで、選んだ部屋へ進むか、たからばこを開ける
+    fn explore(&mut self, command: dungeon_menu::Command) -> Result<(), game_error::GameError> {
+        let Some(exploration) = &mut self.exploration else {
+            return Ok(());
+        };
+        match command {
+            dungeon_menu::Command::OpenChest => {
+                let item = exploration
+                    .open_chest()
+                    .and_then(|rarity| self.master_data.items.random_pick(rarity, 1).pop());
+                self.system_info = match item {
+                    Some(item) => {
+                        let info =
+                            format!("たからばこを　あけた！　{}　を　てにいれた！", item.name);
+                        self.give_item(item);
+                        info
+                    }
+                    None => "たからばこは　からっぽだった。".to_string(),
+                };
+                if let Some(exploration) = &self.exploration {
+                    self.dungeon_menu = Some(dungeon_menu::DungeonMenu::new(exploration));
+                }
+            }
+            dungeon_menu::Command::Move(room) => match exploration.move_to(&room)? {
+                dungeon::Step::Quiet => {
+                    self.system_info = exploration.describe();
+                    self.dungeon_menu = Some(dungeon_menu::DungeonMenu::new(exploration));
+                }
+                dungeon::Step::Encounter => {
+                    let table = exploration.dungeon.encounter_table.clone();
+                    let enemies = self
+                        .master_data
+                        .encounter_tables
+                        .roll(&table, &self.master_data.characters)?;
+                    // 戦闘が終わったら、探索に戻る
+                    self.start_battle_then(enemies, vec![Message::ShowDungeon]);
+                    self.system_info = "てきが　あらわれた！".to_string();
+                }
+                dungeon::Step::Boss(bosses) => {
+                    let enemies = encounter_table::GroupEnemies::Named(bosses)
+                        .pick(&self.master_data.characters)?;
+                    // ボスを倒したら探索を終えて、シナリオの続きに進む
+                    self.exploration = None;
+                    self.start_battle_then(enemies, vec![]);
+                    self.system_info = "ボスが　あらわれた！".to_string();
+                }
+            },
+        }
+        Ok(())
+    }
+
+    // 選んだほうびを受け取る。覚えているスキルは重ねて覚えない
+    fn take_reward(&mut self, reward: roguelike::Reward) {
+        if let Some(run) = &mut self.run {
+            run.rewards.push(reward.to_string());
+        }
+        match reward {
+            roguelike::Reward::Skill(skill) => {
+                if !self.usable_skills.skills.contains(&skill) {
+                    self.usable_skills.skills.push(skill);
+                    self.player.skills = self.usable_skills.clone();
+                }
+            }
+            roguelike::Reward::Item(item) => self.give_item(item),
+        }
+    }
+
+    // ふしぎのとうの挑戦を終えて結果を表示し、旅のゆうしゃに戻す
+    fn finish_run(&mut self, cleared: bool) -> Result<(), game_error::GameError> {
+        let Some(run) = self.run.take() else {
+            return Ok(());
+        };
+        self.system_info = run.summary(cleared);
+        self.player = run.stash.player;
+        self.owned_items = run.stash.owned_items;
+        self.usable_skills = run.stash.usable_skills;
+        self.gold = run.stash.gold;
+        self.encountered_enemies = None;
+        self.reward_menu = None;
+        self.show_next_button = true;
+        Ok(())
+    }
+
+    // 出会った敵との戦闘を、シナリオの次に差し込む。戦闘の後は then に進む
+    fn start_battle_then(&mut self, enemies: Vec<Character>, then: Vec<Message>) {
+        self.dungeon_menu = None;
+        self.encountered_enemies = Some(Characters::for_battle(enemies));
+        self.battle_log.clear();
+        self.show_next_button = true;
+        let next = (self.scenario_idx + 1).min(self.scenario.len());
+        let battle = Message::BattleOperationMenu(battle_operation_menu::Message::Initial);
+        self.scenario
+            .splice(next..next, std::iter::once(battle).chain(then));
+    }
+
+    // 狙う敵の選択へ進む
+    fn open_target_enemy_menu(
+        &mut self,
+        action: battle_ui::Action,
+    ) -> Result<(), game_error::GameError> {
+        let encountered_enemies = self
+            .encountered_enemies
+            .clone()
+            .ok_or(game_error::GameError::NoEncounteredEnemies)?;
+        let menu = target_enemy_menu::TargetEnemyMenu::new(encountered_enemies);
+        self.transition(battle_ui::Screen::Target(menu, action))
+    }
+
+    // エラーを消して立て直す。戦闘中ならコマンドの選択からやり直し、そうでなければシナリオを進められるようにする
+    fn recover_from_error(&mut self) -> Result<(), game_error::GameError> {
+        self.error = None;
+        self.battle_ui.close();
+        self.enemy_turn_queue = vec![];
+        let in_battle = !self.player.is_knocked_out()
+            && self.encountered_enemies.as_ref().is_some_and(|enemies| {
+                enemies
+                    .characters
+                    .iter()
+                    .any(|enemy| !enemy.is_knocked_out())
+            });
+        if in_battle {
+            let menu = battle_operation_menu::BattleOperationMenu::new();
+            self.transition(battle_ui::Screen::Root(menu))?;
+        } else if self.area_select.is_none()
+            && self.dungeon_menu.is_none()
+            && self.reward_menu.is_none()
+            && self.equip_menu.is_none()
+        {
+            // 世界地図やダンジョンなどのメニューを開いていれば、そのまま選び直せる
+            self.show_next_button = true;
+        }
+        Ok(())
+    }
+
+    // キーボードの操作を、表示しているメニューに送る
+    fn on_menu_key(&mut self, key: command_menu::MenuKey) {
+        // エラーの表示中は、決定で立て直す
+        if key == command_menu::MenuKey::Confirm && self.error.is_some() {
+            return self.update(Message::RecoverFromError);
+        }
+        // 文字送りの途中で決定したときは、文字送りを飛ばすだけにする
+        if key == command_menu::MenuKey::Confirm && !self.text_reveal.is_finished() {
+            self.text_reveal.skip();
+            return;
+        }
+        let confirm = key == command_menu::MenuKey::Confirm;
+        let message = match self.battle_ui.current() {
+            Some(battle_ui::Screen::Root(menu)) => {
+                menu.on_key(key).map(Message::BattleOperationMenu)
+            }
+            Some(battle_ui::Screen::Skills(menu)) => menu.on_key(key).map(Message::UseSkillMenu),
+            Some(battle_ui::Screen::Items(menu)) => menu.on_key(key).map(Message::UseItemMenu),
+            Some(battle_ui::Screen::Target(menu, _)) => {
+                menu.on_key(key).map(Message::TargetEnemyMenu)
+            }
+            Some(battle_ui::Screen::Result(_)) | Some(battle_ui::Screen::EnemyTurn(_)) => confirm
+                .then_some(Message::BattleResultMenu(
+                    battle_result_menu::Message::OnClickNext,
+                )),
+            // 戦闘の外のメニューを開いていれば、そのメニューを操作する
+            None => {
+                if let Some(menu) = &self.area_select {
+                    menu.on_key(key).map(Message::AreaSelectMenu)
+                } else if let Some(menu) = &self.dungeon_menu {
+                    menu.on_key(key).map(Message::DungeonMenu)
+                } else if let Some(menu) = &self.reward_menu {
+                    menu.on_key(key).map(Message::RewardMenu)
+                } else if let Some(menu) = &self.equip_menu {
+                    menu.on_key(key).map(Message::EquipMenu)
+                } else {
+                    (confirm && self.show_next_button).then_some(Message::Next)
+                }
+            }
+        };
+        if let Some(message) = message {
+            self.update(message);
+        }
+    }
+
+    // 文字送りで表示する文章。戦闘の結果があればその結果、なければ画面上部の情報
+    fn message_text(&self) -> String {
+        match self.battle_ui.result_menu() {
+            Some(menu) => menu.text(),
+            None => self.system_info.clone(),
+        }
+    }
+
+    // 時刻を進めて演出を更新する。自動送りなら、表示し終えた戦闘の結果を送る
+    fn tick(&mut self, now: Instant) {
+        self.hit_feedback.tick(now);
+        self.text_reveal.tick(now, self.text_speed);
+        if self.auto_advance
+            && self.battle_ui.result_menu().is_some()
+            && self.text_reveal.waited() >= AUTO_ADVANCE_WAIT
+        {
+            self.update(Message::BattleResultMenu(
+                battle_result_menu::Message::OnClickNext,
+            ));
+        }
+    }
+
+    // 戦闘ログの操作
+    fn update_battle_log(&mut self, message: battle_log::Message) {
+        match message {
+            battle_log::Message::OnClickExport => {
+                let path = std::path::PathBuf::from("battle_log.txt");
+                self.system_info = match self.battle_log.export(&path) {
+                    Ok(()) => format!("たたかいの　きろくを　{}　に　かきだした。", path.display()),
+                    Err(error) => format!("きろくを　かきだせなかった：{}", error),
+                };
+            }
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+enum RandomCollection {
+    RandomItemCollection(Rarity, i8),
+    RandomEnemyCollection(Level, i8),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_selected_item() {
+        // テスト用の初期データを作成
+        let mut game = Game::new().unwrap();
+        let test_item = Item {
+            name: "ポーション".to_string(),
+            rarity: Rarity::new(1),
+            effect: Effect::Heal(Ratio { percentage: 0.1 }),
+        };
+        game.selected_item = Some(test_item.clone());
+        game.owned_items = vec![];
+
+        // 処理を実行
+        game.update(Message::GiveSelectedItemForUser);
+
+        // 結果を検証
+        assert_eq!(game.owned_items.len(), 1);
+        assert_eq!(game.owned_items[0].item, test_item);
+        assert_eq!(game.owned_items[0].amount, 1);
+        assert_eq!(
+            game.system_info,
+            format!("{}　を　てにいれた！", test_item.name)
+        );
+
+        // アイテムを追加して再度テスト
+        game.selected_item = Some(test_item.clone());
+        game.update(Message::GiveSelectedItemForUser);
+        assert_eq!(game.owned_items.len(), 1); // 所持アイテム数は変わらない
+        assert_eq!(game.owned_items[0].amount, 2); // 同じアイテムの数が増える
+    }
+
+    #[test]
+    fn test_get_selected_item_no_selection() {
+        // テスト用の初期データを作成
+        let mut game = Game::new().unwrap();
+        game.selected_item = None;
+        game.owned_items = vec![];
+
+        // 処理を実行
+        game.update(Message::GiveSelectedItemForUser);
+
+        // 結果を検証
+        assert_eq!(game.owned_items.len(), 0);
+        assert_eq!(game.system_info, "アイテムが　えらばれて　いない。");
+    }
+
+    #[test]
+    fn test_stat_rank_feeds_attack_and_defence() {
+        let mut character = Character::for_test(1, 10.0, 10.0);
+        assert_eq!(character.add_stat_modifier(StatType::Attack, 2, 3), 2);
+        assert_eq!(character.add_stat_modifier(StatType::Defence, -1, 3), -1);
+
+        assert_eq!(character.attack(), 20.0);
+        assert!((character.defence() - 20.0 / 3.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_stat_rank_is_capped() {
+        let mut character = Character::for_test(1, 10.0, 10.0);
+        assert_eq!(character.add_stat_modifier(StatType::Attack, 4, 3), 4);
+        // 上限を超える分は切り捨てられる
+        assert_eq!(character.add_stat_modifier(StatType::Attack, 4, 3), 2);
+        assert_eq!(character.add_stat_modifier(StatType::Attack, 1, 3), 0);
+        assert_eq!(
+            character.stat_stage(&StatType::Attack),
+            battle_rules::STAT_STAGE_LIMIT
+        );
+
+        // マスタデータに極端な段階が書かれていても、あふれずに上限で止まる
+        assert_eq!(character.add_stat_modifier(StatType::Attack, i8::MAX, 3), 0);
+        assert_eq!(
+            character.add_stat_modifier(StatType::Attack, i8::MIN, 3),
+            -2 * battle_rules::STAT_STAGE_LIMIT
+        );
+    }
+
+    #[test]
+    fn test_dispel_stat_modifiers() {
+        let mut character = Character::for_test(1, 10.0, 10.0);
+        character.add_stat_modifier(StatType::Attack, 2, 3);
+        character.add_stat_modifier(StatType::Defence, -2, 3);
+
+        // 能力低下だけを打ち消す
+        assert!(character.dispel_stat_modifiers(false));
+        assert_eq!(character.stat_stage(&StatType::Attack), 2);
+        assert_eq!(character.stat_stage(&StatType::Defence), 0);
+        assert!(!character.dispel_stat_modifiers(false));
+    }
+
+    fn test_skill(name: &str, effect: Effect) -> Skill {
+        Skill {
+            name: name.into(),
+            rarity: Rarity::new(1),
+            effect,
+            mp_cost: MpCost::default(),
+            cooldown: 0,
+            charge_turns: 0,
+            additional_effects: vec![],
+        }
+    }
+
+    fn heal_skill() -> Skill {
+        test_skill("ヒール", Effect::Heal(Ratio { percentage: 0.1 }))
+    }
+
+    // 敵が 1 体いて、スキルを選び終えて狙う敵を選ぶ画面まで進めた状態
+    fn game_at_target(skill: Skill) -> Game {
+        let mut game = Game::new().unwrap();
+        game.encountered_enemies = Some(Characters::for_battle(vec![Character::for_test(
+            1, 1.0, 1.0,
+        )]));
+        let enemies = game.encountered_enemies.clone().unwrap();
+        let menu = battle_operation_menu::BattleOperationMenu::new();
+        game.transition(battle_ui::Screen::Root(menu)).unwrap();
+        let menu = game.new_use_skill_menu();
+        game.transition(battle_ui::Screen::Skills(menu)).unwrap();
+        let menu = target_enemy_menu::TargetEnemyMenu::new(enemies);
+        game.transition(battle_ui::Screen::Target(
+            menu,
+            battle_ui::Action::Skill(skill),
+        ))
+        .unwrap();
+        game.update(Message::TargetEnemyMenu(
+            target_enemy_menu::Message::OnSelectEnemy(ParticipantId(1)),
+        ));
+        game
+    }
+
+    #[test]
+    fn test_battle_log_keeps_encounter_history() {
+        let mut game = game_at_target(heal_skill());
+
+        // プレイヤーの行動の後、敵が行動してもログは残る
+        game.player_turn(heal_skill(), Some(ParticipantId(1)))
+            .unwrap();
+        let player_events = game.battle_log.events().len();
+        game.update(Message::BattleResultMenu(
+            battle_result_menu::Message::OnClickNext,
+        ));
+        game.update(Message::BattleResultMenu(
+            battle_result_menu::Message::OnClickNext,
+        ));
+
+        assert!(game.battle_log.events().len() > player_events);
+        let enemy = game.encountered_enemies.as_ref().unwrap().characters[0].participant();
+        assert!(game
+            .battle_log
+            .events()
+            .contains(&battle_event::BattleEvent::Waiting { user: enemy }));
+    }
+
+    #[test]
+    fn test_wait_without_mp() {
+        let mut game = Game::new().unwrap();
+        game.player.mp = 0.0;
+        let enemy = Character::for_test(1, 1.0, 1.0);
+        game.encountered_enemies = Some(Characters::for_battle(vec![enemy]));
+        game.update(Message::BattleOperationMenu(
+            battle_operation_menu::Message::Initial,
+        ));
+
+        // MP が尽きても、様子を見てターンを進められる
+        for message in [
+            battle_operation_menu::Message::OnSelectOperation(
+                battle_operation_menu::Operation::Wait,
+            ),
+            battle_operation_menu::Message::OnClickNext,
+        ] {
+            game.update(Message::BattleOperationMenu(message));
+        }
+        assert_eq!(game.error, None);
+        assert!(matches!(
+            game.battle_ui.current(),
+            Some(battle_ui::Screen::Result(_))
+        ));
+        let player = game.player.participant();
+        assert!(game
+            .battle_log
+            .events()
+            .contains(&battle_event::BattleEvent::Waiting { user: player }));
+
+        // 敵の行動を経て、次のターンのメニューに戻る
+        game.update(Message::BattleResultMenu(
+            battle_result_menu::Message::OnClickNext,
+        ));
+        game.update(Message::BattleResultMenu(
+            battle_result_menu::Message::OnClickNext,
+        ));
+        assert!(matches!(
+            game.battle_ui.current(),
+            Some(battle_ui::Screen::Root(_))
+        ));
+    }
+
+    #[test]
+    fn test_damage_starts_hit_feedback() {
+        let effect = Effect::Attack(Probability { percentage: 100 }, Power { value: 1.0 });
+        let skill = test_skill("たいあたり", effect);
+        let mut game = game_at_target(skill.clone());
+
+        game.player_turn(skill, Some(ParticipantId(1))).unwrap();
+
+        assert!(game.hit_feedback.progress(ParticipantId(1)).is_some());
+        assert!(game.hit_feedback.progress(ParticipantId::PLAYER).is_none());
+    }
+
+    #[test]
+    fn test_target_duplicate_enemy_by_id() {
+        let effect = Effect::Attack(Probability { percentage: 100 }, Power { value: 1.0 });
+        let skill = test_skill("たいあたり", effect);
+        let mut game = game_at_target(skill.clone());
+        let enemy = Character::for_test(1, 1.0, 1.0);
+        game.encountered_enemies = Some(Characters::for_battle(vec![enemy.clone(), enemy]));
+
+        // 同じ敵が 2 体いても、2 体目だけを狙える
+        game.player_turn(skill, Some(ParticipantId(2))).unwrap();
+
+        let enemies = &game.encountered_enemies.as_ref().unwrap().characters;
+        assert_eq!(enemies[0].label(), "テストA");
+        assert_eq!(enemies[1].label(), "テストB");
+        assert_eq!(enemies[0].hp, enemies[0].hp_max);
+        assert!(enemies[1].hp < enemies[1].hp_max);
+        assert!(game.hit_feedback.progress(ParticipantId(1)).is_none());
+        assert!(game.hit_feedback.progress(ParticipantId(2)).is_some());
+    }
+
+    #[test]
+    fn test_charge_keeps_retargeted_enemy() {
+        let effect = Effect::Attack(Probability { percentage: 100 }, Power { value: 1.0 });
+        let mut skill = test_skill("ためうち", effect);
+        skill.charge_turns = 1;
+        let mut game = game_at_target(skill.clone());
+        let mut fallen = Character::for_test(1, 1.0, 1.0);
+        fallen.hp = 0.0;
+        fallen.knocked_out = true;
+        let enemy = Character::for_test(1, 1.0, 1.0);
+        game.encountered_enemies = Some(Characters::for_battle(vec![fallen, enemy]));
+
+        // 倒れた敵を狙ってためはじめると、代わりに狙った敵にためたスキルを当てる
+        game.player_turn(skill, Some(ParticipantId(1))).unwrap();
+
+        assert!(game.player.charging.is_some());
+        assert_eq!(game.charge_target, Some(ParticipantId(2)));
+    }
+
+    #[test]
+    fn test_enter_area_after_flags() {
+        let mut game = Game::new().unwrap();
+        game.update(Message::EnterArea("まおうのしろ".into()));
+        assert_eq!(
+            game.error,
+            Some(game_error::GameError::AreaLocked("まおうのしろ".into()))
+        );
+
+        // フラグが立てば、エリアのシナリオが始まる
+        game.update(Message::RecoverFromError);
+        game.story_flags.insert("どうくつを　ぬけた".into());
+        game.update(Message::EnterArea("まおうのしろ".into()));
+        assert!(game.error.is_none());
+        assert_eq!(game.system_info, "まおうのしろに　のりこんだ。");
+        game.update(Message::Next);
+        assert_eq!(game.system_info, "まがまがしい　けはいが　する……");
+        game.update(Message::Next);
+        assert_eq!(
+            game.encountered_enemies.unwrap().characters[0].name,
+            "ドラゴン"
+        );
+    }
+
+    #[test]
+    fn test_explore_dungeon() {
+        let mut game = Game::new().unwrap();
+        let choose = |game: &mut Game, command: dungeon_menu::Command| {
+            game.update(Message::DungeonMenu(
+                dungeon_menu::Message::OnSelectCommand(command),
+            ));
+            game.update(Message::DungeonMenu(dungeon_menu::Message::OnClickNext));
+        };
+        game.update(Message::EnterDungeon("くらやみのどうくつ".into()));
+        assert_eq!(game.system_info, "くらやみのどうくつ　いりぐち");
+
+        // 遭遇したら、戦闘の後に探索へ戻る
+        game.exploration.as_mut().unwrap().dungeon.encounter_rate = 1.0;
+        choose(&mut game, dungeon_menu::Command::Move("ひろま".into()));
+        assert!(game.encountered_enemies.is_some());
+        assert!(game.dungeon_menu.is_none());
+        assert!(matches!(
+            game.scenario[game.scenario_idx + 2],
+            Message::ShowDungeon
+        ));
+        game.update(Message::ShowDungeon);
+
+        // たからばこからアイテムが手に入る
+        game.exploration.as_mut().unwrap().dungeon.encounter_rate = 0.0;
+        choose(&mut game, dungeon_menu::Command::OpenChest);
+        assert_eq!(game.owned_items.len(), 1);
+        assert_eq!(game.owned_items[0].item.rarity, Rarity::new(1));
+
+        // ボスの部屋では、必ずボスと戦う
+        choose(&mut game, dungeon_menu::Command::Move("ほそいみち".into()));
+        choose(&mut game, dungeon_menu::Command::Move("おくのま".into()));
+        assert!(game.exploration.is_none());
+        assert_eq!(game.system_info, "ボスが　あらわれた！");
+        game.update(Message::Next);
+        assert!(matches!(
+            game.battle_ui.current(),
+            Some(battle_ui::Screen::Root(_))
+        ));
+        assert_eq!(
+            game.encountered_enemies.as_ref().unwrap().characters[0].name,
+            "オーク"
+        );
+    }
+
+    #[test]
+    fn test_roguelike_run() {
+        dice::seed(0);
+        let mut game = Game::new().unwrap();
+        game.give_item(game.master_data.items.items[0].clone());
+        let story_skills = game.usable_skills.clone();
+        game.update(Message::StartRun);
+
+        // まっさらなゆうしゃで、1 かいの敵と戦う
+        assert!(game.owned_items.is_empty());
+        assert_eq!(game.run.as_ref().unwrap().floor, 1);
+        let enemies = &game.encountered_enemies.as_ref().unwrap().characters;
+        assert!(enemies.iter().all(|enemy| enemy.level.value == 1));
+
+        // 勝ったら 3 つのほうびから 1 つを選び、次の階へ上る
+        game.update(Message::OfferRewards);
+        assert!(game.encountered_enemies.is_none());
+        assert!(game.frame().menu.unwrap().labels.len() <= 3);
+        game.update(Message::RewardMenu(reward_menu::Message::OnSelectReward(0)));
+        game.update(Message::RewardMenu(reward_menu::Message::OnClickNext));
+        let run = game.run.as_ref().unwrap();
+        assert_eq!(run.floor, 2);
+        assert_eq!(run.defeated, 2);
+        assert_eq!(run.rewards.len(), 1);
+
+        // 倒れたら挑戦は終わり、旅のゆうしゃに戻る
+        let skill = game.usable_skills.skills[0].clone();
+        for message in [
+            Message::BattleOperationMenu(battle_operation_menu::Message::Initial),
+            Message::BattleOperationMenu(battle_operation_menu::Message::OnSelectOperation(
+                battle_operation_menu::Operation::ShowSkills,
+            )),
+            Message::BattleOperationMenu(battle_operation_menu::Message::OnClickNext),
+            Message::UseSkillMenu(use_skill_menu::Message::OnSelectSkill(skill)),
+            Message::UseSkillMenu(use_skill_menu::Message::OnClickNext),
+            Message::TargetEnemyMenu(target_enemy_menu::Message::OnSelectEnemy(ParticipantId(1))),
+            Message::TargetEnemyMenu(target_enemy_menu::Message::OnClickNext),
+        ] {
+            game.update(message);
+        }
+        assert!(game.battle_ui.result_menu().is_some());
+        game.player.hp = 0.0;
+        game.player.knocked_out = true;
+        game.update(Message::BattleResultMenu(
+            battle_result_menu::Message::OnClickNext,
+        ));
+        assert!(game.run.is_none());
+        assert!(game.system_info.starts_with("ゆうしゃは　ちからつきた……"));
+        assert!(!game.player.is_knocked_out());
+        assert_eq!(game.usable_skills, story_skills);
+        assert_eq!(game.owned_items.len(), 1);
+    }
+
+    #[test]
+    fn test_info_is_revealed_and_skipped() {
+        let mut game = Game::new().unwrap();
+        game.update(Message::Info("てきが　あらわれた！".into()));
+        assert_eq!(game.text_reveal.visible(), "");
+
+        game.update(Message::SkipText);
+        assert_eq!(game.text_reveal.visible(), "てきが　あらわれた！");
+        // 飛ばしても、つぎへ ボタンは消えない
+        assert!(game.show_next_button);
+    }
+
+    #[test]
+    fn test_auto_advance_battle_result() {
+        let mut game = game_at_target(heal_skill());
+        game.update(Message::ToggleAutoAdvance(true));
+        game.update(Message::TargetEnemyMenu(
+            target_enemy_menu::Message::OnClickNext,
+        ));
+        game.update(Message::SkipText);
+
+        // すべて表示してから待つと、敵の行動に進む
+        let start = Instant::now();
+        game.update(Message::Tick(start));
+        game.update(Message::Tick(start + AUTO_ADVANCE_WAIT));
+        assert_eq!(game.message_text(), "テスト は ようすを みている。");
+    }
+
+    #[test]
+    fn test_keyboard_only_player_turn() {
+        use battle_ui::Screen;
+        use command_menu::MenuKey;
+        let mut game = Game::new().unwrap();
+        game.update(Message::ChangeTextSpeed(text_reveal::TextSpeed::NoWait));
+        game.encountered_enemies = Some(Characters::for_battle(vec![Character::for_test(
+            1, 1.0, 1.0,
+        )]));
+        game.update(Message::BattleOperationMenu(
+            battle_operation_menu::Message::Initial,
+        ));
+
+        // 行動・スキル・敵を、矢印キーと Enter だけで選ぶ
+        for key in [MenuKey::Down, MenuKey::Confirm] {
+            game.update(Message::MenuKey(key));
+        }
+        assert!(matches!(game.battle_ui.current(), Some(Screen::Skills(_))));
+        for key in [MenuKey::Down, MenuKey::Confirm] {
+            game.update(Message::MenuKey(key));
+        }
+        assert!(matches!(
+            game.battle_ui.current(),
+            Some(Screen::Target(_, _))
+        ));
+        game.update(Message::MenuKey(MenuKey::Down));
+        // 敵はプレイヤーと違う番号で選ばれる
+        assert!(matches!(
+            game.battle_ui.current(),
+            Some(Screen::Target(menu, _)) if menu.enemy == Some(ParticipantId(1))
+        ));
+        game.update(Message::MenuKey(MenuKey::Confirm));
+        assert!(matches!(game.battle_ui.current(), Some(Screen::Result(_))));
+    }
+
+    #[test]
+    fn test_escape_goes_back() {
+        let mut game = game_at_target(heal_skill());
+
+        // 敵の選択からスキルの選択、戦闘操作メニューへと戻る
+        game.update(Message::MenuKey(command_menu::MenuKey::Back));
+        assert!(matches!(
+            game.battle_ui.current(),
+            Some(battle_ui::Screen::Skills(_))
+        ));
+        game.update(Message::MenuKey(command_menu::MenuKey::Back));
+        assert!(matches!(
+            game.battle_ui.current(),
+            Some(battle_ui::Screen::Root(_))
+        ));
+    }
+
+    #[test]
+    fn test_stale_menu_messages_are_ignored() {
+        let mut game = game_at_target(heal_skill());
+        game.update(Message::TargetEnemyMenu(
+            target_enemy_menu::Message::OnClickNext,
+        ));
+
+        // 結果の表示中に、閉じたメニューの操作が届いても何も起きない
+        game.update(Message::UseSkillMenu(use_skill_menu::Message::OnClickNext));
+        game.update(Message::TargetEnemyMenu(
+            target_enemy_menu::Message::OnClickBack,
+        ));
+        assert!(matches!(
+            game.battle_ui.current(),
+            Some(battle_ui::Screen::Result(_))
+        ));
+    }
+
+    #[test]
+    fn test_use_item_in_battle() {
+        let mut game = game_at_target(heal_skill());
+        let potion = Item {
+            name: "ポーション".to_string(),
+            rarity: Rarity::new(1),
+            effect: Effect::Heal(Ratio { percentage: 0.1 }),
+        };
+        game.owned_items = vec![ItemContainer {
+            item: potion.clone(),
+            amount: 1,
+        }];
+
+        game.player_action(battle_ui::Action::Item(potion), Some(ParticipantId(1)))
+            .unwrap();
+
+        // 使ったアイテムはなくなる
+        assert!(game.owned_items.is_empty());
+        assert!(matches!(
+            game.battle_ui.current(),
+            Some(battle_ui::Screen::Result(_))
+        ));
+    }
+
+    #[test]
+    fn test_encounter_with_items_shows_error() {
+        let mut game = Game::new().unwrap();
+        game.update(Message::RandomEncounter(RandomItemCollection(
+            Rarity::new(1),
+            2,
+        )));
+        assert_eq!(game.error, Some(game_error::GameError::NotEnemyCollection));
+
+        // 戦闘中でなければ、立て直した後はシナリオを進められる
+        game.update(Message::RecoverFromError);
+        assert_eq!(game.error, None);
+        assert!(game.show_next_button);
+    }
+
+    #[test]
+    fn test_target_without_selection_shows_error() {
+        let mut game = game_at_target(heal_skill());
+        game.update(Message::TargetEnemyMenu(
+            target_enemy_menu::Message::OnClickBack,
+        ));
+        game.update(Message::UseSkillMenu(
+            use_skill_menu::Message::OnSelectSkill(heal_skill()),
+        ));
+        game.update(Message::UseSkillMenu(use_skill_menu::Message::OnClickNext));
+
+        // 敵を選ばずに決めると、画面はそのままでエラーになる
+        game.update(Message::TargetEnemyMenu(
+            target_enemy_menu::Message::OnClickNext,
+        ));
+        assert_eq!(game.error, Some(game_error::GameError::NoTargetSelected));
+        assert!(matches!(
+            game.battle_ui.current(),
+            Some(battle_ui::Screen::Target(_, _))
+        ));
+
+        // 戦闘中なら、コマンドの選択からやり直す
+        game.update(Message::MenuKey(command_menu::MenuKey::Confirm));
+        assert_eq!(game.error, None);
+        assert!(matches!(
+            game.battle_ui.current(),
+            Some(battle_ui::Screen::Root(_))
+        ));
+    }
+
+    #[test]
+    fn test_skill_menu_without_enemies_shows_error() {
+        let mut game = Game::new().unwrap();
+        game.update(Message::BattleOperationMenu(
+            battle_operation_menu::Message::Initial,
+        ));
+        game.update(Message::BattleOperationMenu(
+            battle_operation_menu::Message::OnSelectOperation(
+                battle_operation_menu::Operation::ShowSkills,
+            ),
+        ));
+        game.update(Message::BattleOperationMenu(
+            battle_operation_menu::Message::OnClickNext,
+        ));
+        game.update(Message::UseSkillMenu(use_skill_menu::Message::OnClickNext));
+        assert_eq!(game.error, Some(game_error::GameError::NoSkillSelected));
+
+        game.update(Message::UseSkillMenu(
+            use_skill_menu::Message::OnSelectSkill(heal_skill()),
+        ));
+        game.update(Message::UseSkillMenu(use_skill_menu::Message::OnClickNext));
+        assert_eq!(
+            game.error,
+            Some(game_error::GameError::NoEncounteredEnemies)
+        );
+    }
+
+    #[test]
+    fn test_rest_at_inn() {
+        let mut game = Game::new().unwrap();
+        game.player.hp_set(1.0);
+        game.player.mp_decrease(5.0);
+        game.player.turn_of_poisoned = 3;
+
+        // ゴールドが足りなければ泊まれない
+        game.gold = 5;
+        game.update(Message::Rest(10));
+        assert_eq!(game.system_info, "ゴールドが　たりない。");
+        assert_eq!(game.player.hp, 1.0);
+
+        // 泊まると HP と MP が回復し、特殊状態も治る
+        game.gold = 12;
+        game.update(Message::Rest(10));
+        assert_eq!(game.gold, 2);
+        assert_eq!(game.player.hp, game.player.hp_max);
+        assert_eq!(game.player.mp, game.player.mp_max);
+        assert_eq!(game.player.turn_of_poisoned, 0);
+        assert_eq!(
+            game.system_info,
+            "10ゴールドを　はらって　やどに　とまった。\nHP と MP が　かいふくした。"
+        );
+    }
+
+    #[test]
+    fn test_equip_outside_battle() {
+        let mut game = Game::new().unwrap();
+        let base_attack = game.player.attack();
+        let base_defence = game.player.defence();
+        game.update(Message::GiveEquipment("どうのつるぎ".into()));
+        game.update(Message::GiveEquipment("いやしのゆびわ".into()));
+        assert_eq!(game.system_info, "いやしのゆびわを　てにいれた。");
+        game.update(Message::GiveEquipment("ひのきのぼう".into()));
+        assert_eq!(
+            game.error,
+            Some(game_error::GameError::UnknownEquipment(
+                "ひのきのぼう".into()
+            ))
+        );
+        game.error = None;
+
+        let choose = |game: &mut Game, command: equip_menu::Command| {
+            game.update(Message::EquipMenu(equip_menu::Message::OnSelectCommand(
+                command,
+            )));
+            game.update(Message::EquipMenu(equip_menu::Message::OnClickNext));
+        };
+        game.update(Message::ShowEquipment);
+        choose(&mut game, equip_menu::Command::Equip("どうのつるぎ".into()));
+        choose(
+            &mut game,
+            equip_menu::Command::Equip("いやしのゆびわ".into()),
+        );
+
+        // そうびの分だけ、こうげきりょくが上がる
+        assert_eq!(game.player.attack(), base_attack + 3.0);
+        assert_eq!(game.player.defence(), base_defence);
+        assert!(game.owned_equipment.is_empty());
+
+        // ゆびわを身につけている間は、ヒールを使える
+        let names = |game: &Game| -> Vec<String> {
+            let skills = game.battle_skills().skills;
+            skills.into_iter().map(|skill| skill.name).collect()
+        };
+        assert!(names(&game).contains(&"ヒール".to_string()));
+        choose(
+            &mut game,
+            equip_menu::Command::Unequip(equipment::Slot::Accessory),
+        );
+        assert_eq!(game.owned_equipment.len(), 1);
+        assert_eq!(names(&game).len(), game.usable_skills.skills.len());
+
+        // 既に持っているそうびは、もう一度は手に入らない
+        game.update(Message::GiveEquipment("いやしのゆびわ".into()));
+        assert_eq!(game.owned_equipment.len(), 1);
+
+        choose(&mut game, equip_menu::Command::Done);
+        assert!(game.equip_menu.is_none());
+    }
+
+    // でたらめなメッセージを作る。中身はマスタデータや出会った敵から選ぶ
+    fn random_message(game: &Game, rng: &mut rand::rngs::StdRng) -> Message {
+        use rand::Rng;
+        let skill = game.master_data.skills.skills.choose(rng).cloned();
+        let item = game.master_data.items.items.choose(rng).cloned();
+        let enemy = game
+            .encountered_enemies
+            .as_ref()
+            .and_then(|enemies| enemies.characters.choose(rng).cloned())
+            .unwrap_or_else(|| Character::for_test(1, 1.0, 1.0));
+        let collection = if rng.gen() {
+            RandomEnemyCollection(Level::new(rng.gen_range(0..3)), rng.gen())
+        } else {
+            RandomItemCollection(Rarity::new(rng.gen_range(0..3)), rng.gen())
+        };
+        let key = [
+            command_menu::MenuKey::Up,
+            command_menu::MenuKey::Down,
+            command_menu::MenuKey::Confirm,
+            command_menu::MenuKey::Back,
+        ][rng.gen_range(0..4)];
+        let operation = [
+            battle_operation_menu::Operation::ShowSkills,
+            battle_operation_menu::Operation::ShowItemContainers,
+            battle_operation_menu::Operation::Wait,
+        ][rng.gen_range(0..3)];
+        let area = ["はじまりのそうげん", "うみ"][rng.gen_range(0..2)];
+        let room = match rng.gen_range(0..3) {
+            0 => dungeon_menu::Command::OpenChest,
+            1 => dungeon_menu::Command::Move("ひろま".into()),
+            _ => dungeon_menu::Command::Move("うみ".into()),
+        };
+        match rng.gen_range(0..47) {
+            0 => Message::Next,
+            1 => Message::Info("テスト".into()),
+            2 => Message::UpdateSelectorAndInfo(collection, "テスト".into()),
+            3 => Message::ShowItemsForPick,
+            4 => Message::HideItemsForPick,
+            5 => match item {
+                Some(item) => Message::WaitingSelectItemByUser(item),
+                None => Message::Next,
+            },
+            6 => Message::GiveSelectedItemForUser,
+            7 => Message::RandomEncounter(collection),
+            8 => Message::BattleOperationMenu(battle_operation_menu::Message::Initial),
+            9 => Message::BattleOperationMenu(battle_operation_menu::Message::OnSelectOperation(
+                operation,
+            )),
+            10 => Message::BattleOperationMenu(battle_operation_menu::Message::OnClickNext),
+            11 => match skill {
+                Some(skill) => Message::UseSkillMenu(use_skill_menu::Message::OnSelectSkill(skill)),
+                None => Message::UseSkillMenu(use_skill_menu::Message::Initial),
+            },
+            12 => Message::UseSkillMenu(use_skill_menu::Message::OnClickNext),
+            13 => Message::UseSkillMenu(use_skill_menu::Message::OnClickBack),
+            14 => match item {
+                Some(item) => Message::UseItemMenu(use_item_menu::Message::OnSelectItem(item)),
+                None => Message::UseItemMenu(use_item_menu::Message::Initial),
+            },
+            15 => Message::UseItemMenu(use_item_menu::Message::OnClickNext),
+            16 => Message::UseItemMenu(use_item_menu::Message::OnClickBack),
+            17 => Message::TargetEnemyMenu(target_enemy_menu::Message::OnSelectEnemy(enemy.id)),
+            18 => Message::TargetEnemyMenu(target_enemy_menu::Message::OnClickNext),
+            19 => Message::TargetEnemyMenu(target_enemy_menu::Message::OnClickBack),
+            20 => Message::BattleResultMenu(battle_result_menu::Message::Initial),
+            21 | 22 => Message::BattleResultMenu(battle_result_menu::Message::OnClickNext),
+            23 => Message::Tick(Instant::now() + Duration::from_millis(rng.gen_range(0..2000))),
+            24 => Message::SkipText,
+            25 => Message::MenuKey(key),
+            26 => Message::ToggleAutoAdvance(rng.gen()),
+            27 => Message::AreaEncounter(area.into()),
+            28 => Message::ShowWorldMap,
+            29 => Message::EnterArea(area.into()),
+            30 => Message::AreaSelectMenu(area_select_menu::Message::OnSelectArea(area.into())),
+            31 => Message::AreaSelectMenu(area_select_menu::Message::OnClickNext),
+            32 => Message::EnterDungeon(["くらやみのどうくつ", "うみ"][rng.gen_range(0..2)].into()),
+            33 => Message::DungeonMenu(dungeon_menu::Message::OnSelectCommand(room)),
+            34 => Message::DungeonMenu(dungeon_menu::Message::OnClickNext),
+            35 => Message::StartRun,
+            36 => Message::OfferRewards,
+            37 => Message::RewardMenu(reward_menu::Message::OnSelectReward(rng.gen_range(0..4))),
+            38 => Message::RewardMenu(reward_menu::Message::OnClickNext),
+            39 => Message::Rest(rng.gen_range(0..30)),
+            40 => Message::GiveEquipment(
+                ["どうのつるぎ", "いやしのゆびわ", "うみ"][rng.gen_range(0..3)].into(),
+            ),
+            41 => Message::ShowEquipment,
+            42 => Message::EquipMenu(equip_menu::Message::OnSelectCommand(
+                [
+                    equip_menu::Command::Equip("どうのつるぎ".into()),
+                    equip_menu::Command::Unequip(equipment::Slot::Weapon),
+                    equip_menu::Command::Done,
+                ][rng.gen_range(0..3)]
+                .clone(),
+            )),
+            43 => Message::EquipMenu(equip_menu::Message::OnClickNext),
+            _ => Message::RecoverFromError,
+        }
+    }
+
+    #[test]
+    fn test_random_messages_do_not_panic() {
+        use rand::SeedableRng;
+        for seed in 0..50 {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            let mut game = Game::new().unwrap();
+            game.update(Message::ChangeTextSpeed(text_reveal::TextSpeed::NoWait));
+            for _ in 0..300 {
+                let message = random_message(&game, &mut rng);
+                game.update(message);
+                // 描画もパニックしない
+                let _ = game.frame();
+                #[cfg(feature = "gui")]
+                let _ = gui::view(&game);
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// ほうびの選択メニュー。
+// ふしぎのとうで戦闘に勝ったあと、並んだほうびから 1 つを選ぶ。
+#[allow(clippy::module_inception)]
+mod reward_menu {
+    use crate::command_menu::{self, CommandItem, MenuFrame, MenuKey};
+    use crate::roguelike::Reward;
+    #[cfg(feature = "gui")]
+    use iced::widget::Column;
+    #[cfg(feature = "gui")]
+    use iced::Element;
+
+    #[derive(Debug, Clone)]
+    pub enum Message {
+        OnSelectReward(usize), // 並んだほうびの番号
+        OnClickNext,
+    }
+
+    const TITLE: &str = "ほうびを　ひとつ　えらぼう";
+
+    #[derive(Debug, Clone)]
+    pub struct RewardMenu {
+        rewards: Vec<Reward>,
+        // 選択しているほうびの番号
+        pub(crate) reward: Option<usize>,
+    }
+
+    impl RewardMenu {
+        pub fn new(rewards: Vec<Reward>) -> Self {
+            RewardMenu {
+                rewards,
+                reward: None,
+            }
+        }
+
+        pub fn update(&mut self, message: Message) {
+            match message {
+                Message::OnSelectReward(idx) => {
+                    // 選択しているほうびを更新する
+                    self.reward = Some(idx).filter(|idx| *idx < self.rewards.len());
+                }
+                Message::OnClickNext => {
+                    // 何もしない
+                }
+            }
+        }
+
+        // 選んだほうび
+        pub fn selected(&self) -> Option<&Reward> {
+            self.rewards.get(self.reward?)
+        }
+
+        // ほうびを選択するコマンドメニューの項目
+        fn items(&self) -> Vec<CommandItem<Message>> {
+            self.rewards
+                .iter()
+                .enumerate()
+                .map(|(idx, reward)| CommandItem {
+                    label: reward.to_string(),
+                    on_select: Some(Message::OnSelectReward(idx)),
+                })
+                .collect()
+        }
+
+        // キーボードの操作を、このメニューのメッセージに変換する
+        pub fn on_key(&self, key: MenuKey) -> Option<Message> {
+            match key {
+                MenuKey::Up | MenuKey::Down => {
+                    let selectable = vec![true; self.rewards.len()];
+                    let idx = command_menu::move_cursor(self.reward, &selectable, key)?;
+                    Some(Message::OnSelectReward(idx))
+                }
+                MenuKey::Confirm => self.reward.map(|_| Message::OnClickNext),
+                // ほうびは必ず 1 つ選ぶ
+                MenuKey::Back => None,
+            }
+        }
+
+        #[cfg(feature = "gui")]
+        pub fn view(&self) -> Element<'_, Message> {
+            let mut column = Column::new();
+
+            column = column.push(TITLE);
+
+            // ほうびを選択するコマンドメニューの作成
+            column = column.push(command_menu::view(self.items(), self.reward));
+
+            let confirm = iced::widget::button("これを　もらう");
+            match self.reward {
+                // ほうびが選択されている場合だけ、次へ進める
+                Some(_) => column = column.push(confirm.on_press(Message::OnClickNext)),
+                None => column = column.push(confirm),
+            }
+
+            column.into()
+        }
+
+        // 画面に依存しないメニューの内容
+        pub fn frame(&self) -> MenuFrame {
+            command_menu::frame(TITLE, self.items(), self.reward)
+        }
+    }
+}
+
+pub use reward_menu::Message;
+pub use reward_menu::RewardMenu;
@@ -0,0 +1,201 @@
+// ふしぎのとう。
+// 階を上るごとに敵のレベルが上がり、戦闘に勝つと 3 つのほうびから 1 つを選べる。
+// ゆうしゃは毎回まっさらな状態で挑み、倒れたらそこまで。旅のゆうしゃは、とうを出ると元に戻る。
+#[allow(clippy::module_inception)]
+mod roguelike {
+    use crate::battle_rules::Character;
+    use crate::encounter_table::GroupEnemies;
+    use crate::game_error::GameError;
+    use crate::{dice, Characters, ItemContainer, Items, Level, Rarity, Skills};
+    use crate::{Item, Skill};
+    use rand::seq::SliceRandom;
+    use serde::Deserialize;
+
+    // とうの決まり。省略した項目は既定の値になる
+    #[derive(Debug, Clone, Deserialize)]
+    #[serde(default)]
+    pub struct RunRules {
+        pub floors: u8,     // 最上階。この階の敵を倒すとクリア
+        pub enemies: usize, // 1 つの階で出会う敵の数
+        pub choices: usize, // 選べるほうびの数
+    }
+
+    impl Default for RunRules {
+        fn default() -> Self {
+            RunRules {
+                floors: 5,
+                enemies: 2,
+                choices: 3,
+            }
+        }
+    }
+
+    // 戦闘に勝ったときのほうび
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Reward {
+        Skill(Skill),
+        Item(Item),
+    }
+
+    impl std::fmt::Display for Reward {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                Reward::Skill(skill) => write!(f, "スキル：{}", skill.name),
+                Reward::Item(item) => write!(f, "アイテム：{}", item.name),
+            }
+        }
+    }
+
+    // とうに入る前のゆうしゃ。とうを出たら元に戻す
+    #[derive(Debug, Clone)]
+    pub struct Stash {
+        pub player: Box<Character>,
+        pub owned_items: Vec<ItemContainer>,
+        pub usable_skills: Skills,
+        pub gold: u32,
+    }
+
+    // 挑戦中のとう
+    #[derive(Debug, Clone)]
+    pub struct Run {
+        pub rules: RunRules,
+        pub floor: u8,            // いまの階。上りはじめる前は 0
+        pub defeated: usize,      // 倒した敵の数
+        pub rewards: Vec<String>, // 選んだほうび
+        pub stash: Stash,
+    }
+
+    impl Run {
+        pub fn new(rules: RunRules, stash: Stash) -> Self {
+            Run {
+                rules,
+                floor: 0,
+                defeated: 0,
+                rewards: vec![],
+                stash,
+            }
+        }
+
+        // 階の敵のレベル。上の階ほど強い
+        pub fn level(&self) -> Level {
+            Level::new(self.floor)
+        }
+
+        // ほうびのレアリティ。2 階ごとに上がる
+        pub fn reward_rarity(&self) -> Rarity {
+            Rarity::new(1 + self.floor.saturating_sub(1) / 2)
+        }
+
+        // 階で出会う敵。その階のレベルの敵がいなければ、下のレベルから選ぶ
+        pub fn enemies(&self, characters: &Characters) -> Result<Vec<Character>, GameError> {
+            for level in (1..=self.level().value).rev() {
+                let enemies = GroupEnemies::Levels {
+                    min: Level::new(level),
+                    max: Level::new(level),
+                    count: self.rules.enemies,
+                }
+                .pick(characters)?;
+                if !enemies.is_empty() {
+                    return Ok(enemies);
+                }
+            }
+            Err(GameError::NoEncounteredEnemies)
+        }
+
+        // 選べるほうび。スキルとアイテムを混ぜて選び、足りなければ低いレアリティから選ぶ
+        pub fn offer(&self, skills: &Skills, items: &Items) -> Vec<Reward> {
+            for rarity in (1..=self.reward_rarity().value).rev() {
+                let rarity = Rarity::new(rarity);
+                let mut rewards: Vec<Reward> = skills
+                    .random_pick(rarity.clone(), self.rules.choices)
+                    .into_iter()
+                    .map(Reward::Skill)
+                    .chain(
+                        items
+                            .random_pick(rarity, self.rules.choices)
+                            .into_iter()
+                            .map(Reward::Item),
+                    )
+                    .collect();
+                if !rewards.is_empty() {
+                    rewards.shuffle(&mut dice::rng());
+                    rewards.truncate(self.rules.choices);
+                    return rewards;
+                }
+            }
+            vec![]
+        }
+
+        // 挑戦の結果。cleared なら最上階までクリアした
+        pub fn summary(&self, cleared: bool) -> String {
+            let result = match cleared {
+                true => "ふしぎのとうを　せいは　した！",
+                false => "ゆうしゃは　ちからつきた……",
+            };
+            let rewards = match self.rewards.is_empty() {
+                true => "なし".to_string(),
+                false => self.rewards.join("、"),
+            };
+            [
+                result.to_string(),
+                format!(
+                    "とうたつ：{}かい　／　{}かい",
+                    self.floor.min(self.rules.floors),
+                    self.rules.floors
+                ),
+                format!("たおした　てき：{}たい", self.defeated),
+                format!("ほうび：{}", rewards),
+            ]
+            .join("\n")
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn run(floor: u8) -> Run {
+            let stash = Stash {
+                player: Box::new(Character::for_test(1, 1.0, 1.0)),
+                owned_items: vec![],
+                usable_skills: Skills { skills: vec![] },
+                gold: 0,
+            };
+            let mut run = Run::new(RunRules::default(), stash);
+            run.floor = floor;
+            run
+        }
+
+        #[test]
+        fn test_floors_escalate() {
+            assert_eq!(run(1).reward_rarity(), Rarity::new(1));
+            assert_eq!(run(3).reward_rarity(), Rarity::new(2));
+            assert_eq!(run(5).reward_rarity(), Rarity::new(3));
+
+            // 4 階のレベルの敵がいなければ、下のレベルから選ぶ
+            let enemy = |level: u8| Character::for_test(level, 1.0, 1.0);
+            let characters = Characters {
+                characters: vec![enemy(1), enemy(3)],
+            };
+            let enemies = run(4).enemies(&characters).unwrap();
+            assert_eq!(enemies.len(), 2);
+            assert!(enemies.iter().all(|enemy| enemy.level.value == 3));
+        }
+
+        #[test]
+        fn test_summary() {
+            let mut run = run(2);
+            run.defeated = 3;
+            run.rewards = vec!["スキル：ヒール".into()];
+            assert_eq!(
+                run.summary(false),
+                "ゆうしゃは　ちからつきた……\nとうたつ：2かい　／　5かい\nたおした　てき：3たい\nほうび：スキル：ヒール"
+            );
+        }
+    }
+}
+
+pub use roguelike::Reward;
+pub use roguelike::Run;
+pub use roguelike::RunRules;
+pub use roguelike::Stash;
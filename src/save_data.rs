@@ -0,0 +1,198 @@
+// ぼうけんの記録。
+// やどやで休むたびに、フラグ・ゴールド・ゆうしゃの状態・持ち物・そうびを YAML に書き出す。
+// 次に遊ぶときは記録を読み込み、世界地図からつづきを遊べる。
+#[allow(clippy::module_inception)]
+mod save_data {
+    use crate::equipment::Equipment;
+    use crate::{Game, ItemContainer, Message, Skills};
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeSet;
+    use std::path::{Path, PathBuf};
+
+    // ウィンドウ版とターミナル版で使う記録のファイル
+    const SAVE_FILE: &str = "save.yml";
+
+    #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+    pub struct SaveData {
+        pub story_flags: BTreeSet<String>,
+        pub gold: u32,
+        pub hp: f32,
+        pub mp: f32,
+        pub skills: Vec<String>, // 覚えているスキルの名前
+        pub items: Vec<SavedItem>,
+        #[serde(default)]
+        pub equipped: Vec<String>, // 身につけているそうびの名前
+        #[serde(default)]
+        pub owned_equipment: Vec<String>, // 身につけていないそうびの名前
+    }
+
+    // 持っているアイテムの名前と数
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct SavedItem {
+        pub name: String,
+        pub amount: usize,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum SaveError {
+        Io(String),     // ファイルを読み書きできない
+        Format(String), // 記録の形式が正しくない
+    }
+
+    impl std::fmt::Display for SaveError {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                SaveError::Io(error) => write!(f, "ファイルを　よみかき　できない：{}", error),
+                SaveError::Format(error) => write!(f, "きろくが　こわれている：{}", error),
+            }
+        }
+    }
+
+    impl SaveData {
+        pub fn read(path: &Path) -> Result<SaveData, SaveError> {
+            let text =
+                std::fs::read_to_string(path).map_err(|error| SaveError::Io(error.to_string()))?;
+            serde_yaml::from_str(&text).map_err(|error| SaveError::Format(error.to_string()))
+        }
+
+        pub fn write(&self, path: &Path) -> Result<(), SaveError> {
+            let text = serde_yaml::to_string(self)
+                .map_err(|error| SaveError::Format(error.to_string()))?;
+            std::fs::write(path, text).map_err(|error| SaveError::Io(error.to_string()))
+        }
+    }
+
+    impl Game {
+        // やどやで記録を残すようにし、記録があれば読み込んでつづきから始める
+        pub fn with_autosave(self) -> Self {
+            self.with_save_file(PathBuf::from(SAVE_FILE))
+        }
+
+        pub(crate) fn with_save_file(mut self, path: PathBuf) -> Self {
+            if path.exists() {
+                match SaveData::read(&path) {
+                    Ok(save_data) => self.restore(save_data),
+                    Err(error) => {
+                        self.system_info = format!("きろくを　よめなかった。{}", error);
+                    }
+                }
+            }
+            self.save_path = Some(path);
+            self
+        }
+
+        // 記録を残す。記録のファイルがなければ何もしない
+        pub(crate) fn autosave(&self) -> Option<Result<(), SaveError>> {
+            let path = self.save_path.as_ref()?;
+            Some(self.save_data().write(path))
+        }
+
+        fn save_data(&self) -> SaveData {
+            SaveData {
+                story_flags: self.story_flags.clone(),
+                gold: self.gold,
+                hp: self.player.hp,
+                mp: self.player.mp,
+                skills: self
+                    .usable_skills
+                    .skills
+                    .iter()
+                    .map(|skill| skill.name.clone())
+                    .collect(),
+                items: self
+                    .owned_items
+                    .iter()
+                    .map(|container| SavedItem {
+                        name: container.item.name.clone(),
+                        amount: container.amount,
+                    })
+                    .collect(),
+                equipped: names(self.player.equipment.iter()),
+                owned_equipment: names(self.owned_equipment.iter()),
+            }
+        }
+
+        // 記録を読み込み、世界地図から始める。マスタデータにないスキルやアイテム、そうびは捨てる
+        fn restore(&mut self, save_data: SaveData) {
+            let find_skill = |name: &String| {
+                let skills = &self.master_data.skills.skills;
+                skills.iter().find(|skill| &skill.name == name).cloned()
+            };
+            self.usable_skills = Skills {
+                skills: save_data.skills.iter().filter_map(find_skill).collect(),
+            };
+            self.player.skills = self.usable_skills.clone();
+            self.player.hp = save_data.hp.min(self.player.hp_max);
+            self.player.mp = save_data.mp.min(self.player.mp_max);
+            self.owned_items = save_data
+                .items
+                .iter()
+                .filter_map(|saved| {
+                    let items = &self.master_data.items.items;
+                    let item = items.iter().find(|item| item.name == saved.name)?;
+                    Some(ItemContainer {
+                        item: item.clone(),
+                        amount: saved.amount,
+                    })
+                })
+                .collect();
+            let equipments = &self.master_data.equipments;
+            for name in &save_data.equipped {
+                if let Some(equipment) = equipments.find(name) {
+                    self.player.equipment.equip(equipment.clone());
+                }
+            }
+            self.owned_equipment = save_data
+                .owned_equipment
+                .iter()
+                .filter_map(|name| equipments.find(name).cloned())
+                .collect();
+            self.story_flags = save_data.story_flags;
+            self.gold = save_data.gold;
+            let info = "ぼうけんの　つづきから　はじめる。".to_string();
+            self.system_info = info.clone();
+            self.scenario = vec![Message::Info(info), Message::ShowWorldMap];
+            self.scenario_idx = 0;
+        }
+    }
+
+    // そうびの名前の並び
+    fn names<'a>(equipments: impl Iterator<Item = &'a Equipment>) -> Vec<String> {
+        equipments.map(|equipment| equipment.name.clone()).collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_autosave_and_continue() {
+            let path =
+                std::env::temp_dir().join(format!("rustern-save-{}.yml", std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            let mut game = Game::new().unwrap().with_save_file(path.clone());
+            game.story_flags.insert("たびだち".into());
+            game.gold = 42;
+            game.give_item(game.master_data.items.items[0].clone());
+            game.update(Message::GiveEquipment("どうのつるぎ".into()));
+            game.update(Message::GiveEquipment("かわのよろい".into()));
+            let sword = game.owned_equipment.remove(0);
+            game.player.equipment.equip(sword);
+            game.autosave().unwrap().unwrap();
+
+            // 記録を読み込むと、世界地図から同じ状態で始まる
+            let game = Game::new().unwrap().with_save_file(path.clone());
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(game.system_info, "ぼうけんの　つづきから　はじめる。");
+            assert!(game.story_flags.contains("たびだち"));
+            assert_eq!(game.gold, 42);
+            assert_eq!(game.owned_items.len(), 1);
+            assert_eq!(
+                game.player.equipment.weapon.as_ref().unwrap().name,
+                "どうのつるぎ"
+            );
+            assert_eq!(game.owned_equipment[0].name, "かわのよろい");
+            assert!(matches!(game.scenario[1], Message::ShowWorldMap));
+        }
+    }
+}
@@ -0,0 +1,305 @@
+// 同じパーティと敵の組み合わせで、シードを変えながら何度も戦闘して、結果を集計する。
+// 両者とも AI が行動するので、スキルや敵の強さの釣り合いを数字で確かめられる。
+#[allow(clippy::module_inception)]
+mod simulation {
+    use crate::battle_event::BattleEvent;
+    use crate::battle_runner::{self, BattleRecord, Encounter, Outcome, Party, Policy, RunError};
+    use crate::{dice, MasterData};
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    // seed から seed + battles - 1 までのシードで、1 回ずつ戦闘する
+    pub fn simulate(
+        master_data: &MasterData,
+        party: &Party,
+        encounter: &Encounter,
+        battles: usize,
+        seed: u64,
+    ) -> Result<Vec<BattleRecord>, RunError> {
+        (0..battles as u64)
+            .map(|offset| {
+                dice::seed(seed.wrapping_add(offset));
+                battle_runner::run_battle(master_data, party, encounter, &Policy::Ai)
+            })
+            .collect()
+    }
+
+    // 値の散らばり。percentile は小さい方から数えた位置の値
+    #[derive(Debug, Clone, Default, PartialEq, Serialize)]
+    pub struct Distribution {
+        pub count: usize,
+        pub mean: f32,
+        pub min: f32,
+        pub p10: f32,
+        pub p50: f32,
+        pub p90: f32,
+        pub max: f32,
+    }
+
+    impl Distribution {
+        pub fn of(mut samples: Vec<f32>) -> Distribution {
+            if samples.is_empty() {
+                return Distribution::default();
+            }
+            samples.sort_by(f32::total_cmp);
+            let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+            Distribution {
+                count: samples.len(),
+                mean: samples.iter().sum::<f32>() / samples.len() as f32,
+                min: samples[0],
+                p10: percentile(10),
+                p50: percentile(50),
+                p90: percentile(90),
+                max: samples[samples.len() - 1],
+            }
+        }
+    }
+
+    // スキルごとの集計。同じスキルでも、使った者ごとに分ける
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct SkillUsage {
+        pub user: String,
+        pub skill: String,
+        pub uses: usize,
+        pub uses_per_battle: f32,
+        pub hit_rate: f32,        // 失敗しなかった割合
+        pub damage: Distribution, // 1 回当たったときのダメージ
+    }
+
+    // 集計の結果
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct SimulationReport {
+        pub battles: usize,
+        pub outcomes: BTreeMap<Outcome, usize>, // 結末ごとの回数
+        pub win_rate: f32,
+        pub average_turns: f32,
+        pub damage_dealt: Distribution, // 1 戦闘でプレイヤーが与えたダメージの合計
+        pub damage_taken: Distribution, // 1 戦闘でプレイヤーが受けたダメージの合計
+        pub skills: Vec<SkillUsage>,
+    }
+
+    #[derive(Default)]
+    struct SkillTally {
+        uses: usize,
+        misses: usize,
+        damage: Vec<f32>,
+    }
+
+    impl SimulationReport {
+        pub fn of(records: &[BattleRecord]) -> SimulationReport {
+            let mut outcomes = BTreeMap::new();
+            let mut dealt = vec![];
+            let mut taken = vec![];
+            let mut tallies: BTreeMap<(String, String), SkillTally> = BTreeMap::new();
+            for record in records {
+                *outcomes.entry(record.outcome).or_insert(0) += 1;
+                let (battle_dealt, battle_taken) = tally_events(record, &mut tallies);
+                dealt.push(battle_dealt);
+                taken.push(battle_taken);
+            }
+
+            let battles = records.len();
+            let per_battle = |count: usize| {
+                if battles == 0 {
+                    0.0
+                } else {
+                    count as f32 / battles as f32
+                }
+            };
+            let victories = records
+                .iter()
+                .filter(|record| record.outcome == Outcome::Victory)
+                .count();
+            let turns = records.iter().map(|record| record.turns).sum();
+            let skills = tallies
+                .into_iter()
+                .map(|((user, skill), tally)| SkillUsage {
+                    user,
+                    skill,
+                    uses: tally.uses,
+                    uses_per_battle: per_battle(tally.uses),
+                    hit_rate: 1.0 - tally.misses as f32 / tally.uses.max(1) as f32,
+                    damage: Distribution::of(tally.damage),
+                })
+                .collect();
+            SimulationReport {
+                battles,
+                outcomes,
+                win_rate: per_battle(victories),
+                average_turns: per_battle(turns),
+                damage_dealt: Distribution::of(dealt),
+                damage_taken: Distribution::of(taken),
+                skills,
+            }
+        }
+
+        // 表にした文章
+        pub fn lines(&self) -> Vec<String> {
+            let mut lines = vec![format!("たたかった　かいすう: {}", self.battles)];
+            for (outcome, count) in &self.outcomes {
+                lines.push(format!("　{}: {}", outcome, count));
+            }
+            lines.push(format!("しょうりりつ: {:.1}%", self.win_rate * 100.0));
+            lines.push(format!("へいきん　ターン: {:.2}", self.average_turns));
+            lines.push(format!(
+                "あたえた　ダメージ: {}",
+                distribution_text(&self.damage_dealt)
+            ));
+            lines.push(format!(
+                "うけた　ダメージ: {}",
+                distribution_text(&self.damage_taken)
+            ));
+            lines.push("スキル:".to_string());
+            for usage in &self.skills {
+                lines.push(format!(
+                    "　{} の {}: {} かい ({:.2}/せんとう)　めいちゅう {:.1}%　ダメージ {}",
+                    usage.user,
+                    usage.skill,
+                    usage.uses,
+                    usage.uses_per_battle,
+                    usage.hit_rate * 100.0,
+                    distribution_text(&usage.damage)
+                ));
+            }
+            lines
+        }
+    }
+
+    fn distribution_text(distribution: &Distribution) -> String {
+        format!(
+            "へいきん {:.1} (さいしょう {:.1} / 10% {:.1} / 50% {:.1} / 90% {:.1} / さいだい {:.1})",
+            distribution.mean,
+            distribution.min,
+            distribution.p10,
+            distribution.p50,
+            distribution.p90,
+            distribution.max
+        )
+    }
+
+    // できごとをスキルごとに数え、プレイヤーが与えたダメージと受けたダメージの合計を返す。
+    // ダメージは直前に使われたスキルのものとして数える
+    fn tally_events(
+        record: &BattleRecord,
+        tallies: &mut BTreeMap<(String, String), SkillTally>,
+    ) -> (f32, f32) {
+        let mut dealt = 0.0;
+        let mut taken = 0.0;
+        let mut current = None;
+        for event in &record.events {
+            let damage = match event {
+                BattleEvent::SkillUsed { user, skill } => {
+                    let key = (user.name.clone(), skill.clone());
+                    tallies.entry(key.clone()).or_default().uses += 1;
+                    current = Some(key);
+                    continue;
+                }
+                BattleEvent::Miss { .. } => {
+                    if let Some(key) = &current {
+                        tallies.entry(key.clone()).or_default().misses += 1;
+                    }
+                    continue;
+                }
+                // 倒したときに余ったダメージは数えない
+                BattleEvent::Damage { target, lost, .. } => {
+                    if let Some(key) = &current {
+                        tallies.entry(key.clone()).or_default().damage.push(*lost);
+                    }
+                    Some((target, *lost))
+                }
+                BattleEvent::StatusDamage { target, amount, .. } => Some((target, *amount)),
+                BattleEvent::Recoil { user, amount } => Some((user, *amount)),
+                _ => None,
+            };
+            if let Some((target, amount)) = damage {
+                if target.id == record.player.id {
+                    taken += amount;
+                } else {
+                    dealt += amount;
+                }
+            }
+        }
+        (dealt, taken)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn master_data() -> MasterData {
+            MasterData::load(&crate::default_data_path().unwrap()).unwrap()
+        }
+
+        #[test]
+        fn test_distribution() {
+            let distribution = Distribution::of((1..=11).rev().map(|n| n as f32).collect());
+            assert_eq!(distribution.count, 11);
+            assert_eq!(distribution.mean, 6.0);
+            assert_eq!(distribution.min, 1.0);
+            assert_eq!(distribution.p10, 2.0);
+            assert_eq!(distribution.p50, 6.0);
+            assert_eq!(distribution.p90, 10.0);
+            assert_eq!(distribution.max, 11.0);
+            assert_eq!(Distribution::of(vec![]), Distribution::default());
+        }
+
+        #[test]
+        fn test_overkill_is_not_counted() {
+            let player = crate::battle_event::Participant::from("ゆうしゃ");
+            let record = BattleRecord {
+                outcome: Outcome::Victory,
+                turns: 1,
+                player: player.clone(),
+                events: vec![
+                    BattleEvent::SkillUsed {
+                        user: player,
+                        skill: "ファイアボール".into(),
+                    },
+                    BattleEvent::Damage {
+                        target: crate::battle_event::Participant {
+                            id: crate::ParticipantId(1),
+                            name: "スライム".into(),
+                            label: "スライム".into(),
+                        },
+                        amount: 30.0,
+                        lost: 5.0,
+                        hp: 0.0,
+                    },
+                ],
+            };
+            let mut tallies = BTreeMap::new();
+
+            // 残り 5 の HP に 30 のダメージを与えても、数えるのは 5 だけ
+            assert_eq!(tally_events(&record, &mut tallies), (5.0, 0.0));
+        }
+
+        #[test]
+        fn test_simulate_report() {
+            let master_data = master_data();
+            let party = Party::parse("ファイアボール,アクアボール");
+            let encounter = Encounter::parse("スライム").unwrap();
+            let records = simulate(&master_data, &party, &encounter, 20, 1).unwrap();
+            let report = SimulationReport::of(&records);
+
+            assert_eq!(report.battles, 20);
+            assert_eq!(report.outcomes.values().sum::<usize>(), 20);
+            assert!((0.0..=1.0).contains(&report.win_rate));
+            assert!(report.average_turns >= 1.0);
+            assert!(report.damage_dealt.max > 0.0);
+            let used: usize = report
+                .skills
+                .iter()
+                .filter(|usage| usage.user == "ゆうしゃ")
+                .map(|usage| usage.uses)
+                .sum();
+            assert!(used >= 20);
+            // 同じシードなら、同じ集計になる
+            let again = simulate(&master_data, &party, &encounter, 20, 1).unwrap();
+            assert_eq!(report, SimulationReport::of(&again));
+        }
+    }
+}
+
+pub use simulation::simulate;
+pub use simulation::Distribution;
+pub use simulation::SimulationReport;
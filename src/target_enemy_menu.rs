@@ -1,25 +1,38 @@
+#[allow(clippy::module_inception)]
 mod target_enemy_menu {
-    use crate::{Character, Characters, Level, Rarity};
-    use iced::widget::{pick_list, Column};
+    use crate::command_menu::{self, CommandItem, MenuFrame, MenuKey};
+    use crate::{Characters, ParticipantId};
+    #[cfg(feature = "gui")]
+    use iced::widget::Column;
+    #[cfg(feature = "gui")]
     use iced::Element;
 
+    const TITLE: &str = "どの　てきを　ねらう？";
+
+    #[allow(dead_code)]
     #[derive(Debug, Clone)]
     pub enum Message {
         Initial,
-        OnSelectEnemy(Character),
+        OnSelectEnemy(ParticipantId), // 同じ名前の敵がいても、番号で見分ける
         OnClickNext,
         OnClickBack,
     }
 
     pub struct TargetEnemyMenu {
         enemies: Characters,
-        pub(crate) enemy: Option<Character>,
+        pub(crate) enemy: Option<ParticipantId>,
     }
 
     impl TargetEnemyMenu {
         pub(crate) fn new(enemies: Characters) -> Self {
+            // 倒れた敵は狙えない
+            let characters = enemies
+                .characters
+                .into_iter()
+                .filter(|enemy| !enemy.is_knocked_out())
+                .collect();
             TargetEnemyMenu {
-                enemies,
+                enemies: Characters { characters },
                 enemy: None,
             }
         }
@@ -30,9 +43,9 @@ mod target_enemy_menu {
                     // 何も選択していない状態にする
                     self.enemy = None;
                 }
-                Message::OnSelectEnemy(enemy) => {
+                Message::OnSelectEnemy(id) => {
                     // 選択している敵を更新する
-                    self.enemy = Some(enemy)
+                    self.enemy = Some(id)
                 }
                 Message::OnClickNext => {}
                 Message::OnClickBack => {
@@ -42,29 +55,57 @@ mod target_enemy_menu {
             }
         }
 
-        pub fn view(&self) -> Element<Message> {
-            let mut column = Column::new();
-            column = column.push("どの　てきを　ねらう？");
-            column = column.push(pick_list(
-                self.enemies.characters.clone(),
-                self.enemy.clone(),
-                Message::OnSelectEnemy,
-            ));
+        // 選択している敵の番号
+        fn cursor(&self) -> Option<usize> {
+            self.enemies.position_of(self.enemy?)
+        }
 
-            match &self.enemy {
-                Some(_skill) => {
-                    // 敵が選択されている場合、次へ進むためのボタンを表示する
-                    let confirm =
-                        iced::widget::button("この　てきで　よい").on_press(Message::OnClickNext);
-                    column = column.push(confirm);
+        fn items(&self) -> Vec<CommandItem<Message>> {
+            self.enemies
+                .characters
+                .iter()
+                .map(|enemy| CommandItem {
+                    label: enemy.to_string(),
+                    on_select: Some(Message::OnSelectEnemy(enemy.id)),
+                })
+                .collect()
+        }
+
+        // キーボードの操作を、このメニューのメッセージに変換する
+        pub fn on_key(&self, key: MenuKey) -> Option<Message> {
+            match key {
+                MenuKey::Up | MenuKey::Down => {
+                    let selectable = vec![true; self.enemies.characters.len()];
+                    let idx = command_menu::move_cursor(self.cursor(), &selectable, key)?;
+                    Some(Message::OnSelectEnemy(self.enemies.characters[idx].id))
                 }
-                None => {}
+                MenuKey::Confirm => self.enemy.as_ref().map(|_| Message::OnClickNext),
+                MenuKey::Back => Some(Message::OnClickBack),
+            }
+        }
+
+        #[cfg(feature = "gui")]
+        pub fn view(&self) -> Element<'_, Message> {
+            let mut column = Column::new();
+            column = column.push(TITLE);
+            column = column.push(command_menu::view(self.items(), self.cursor()));
+
+            if self.enemy.is_some() {
+                // 敵が選択されている場合、次へ進むためのボタンを表示する
+                let confirm =
+                    iced::widget::button("この　てきで　よい").on_press(Message::OnClickNext);
+                column = column.push(confirm);
             }
 
             // 戻るボタン
             column = column.push(iced::widget::button("もどる").on_press(Message::OnClickBack));
             column.into()
         }
+
+        // 画面に依存しないメニューの内容
+        pub fn frame(&self) -> MenuFrame {
+            command_menu::frame(TITLE, self.items(), self.cursor())
+        }
     }
 }
 
@@ -0,0 +1,166 @@
+// 文章を 1 文字ずつ表示する演出。
+// 時刻は描画ごとの Tick で受け取り、経過時間と文字の速さから表示する文字数を決める。
+#[allow(clippy::module_inception)]
+mod text_reveal {
+    use std::time::{Duration, Instant};
+
+    // 文字を表示する速さ
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))] // ウィンドウの設定からだけ選ばれる
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    pub enum TextSpeed {
+        Slow,
+        #[default]
+        Normal,
+        Fast,
+        NoWait, // 一度にすべて表示する
+    }
+
+    impl TextSpeed {
+        #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+        pub const ALL: [TextSpeed; 4] = [
+            TextSpeed::Slow,
+            TextSpeed::Normal,
+            TextSpeed::Fast,
+            TextSpeed::NoWait,
+        ];
+
+        // 1 秒あたりに表示する文字数。None なら待たずに表示する
+        fn chars_per_second(&self) -> Option<f32> {
+            match self {
+                TextSpeed::Slow => Some(15.0),
+                TextSpeed::Normal => Some(30.0),
+                TextSpeed::Fast => Some(60.0),
+                TextSpeed::NoWait => None,
+            }
+        }
+    }
+
+    impl std::fmt::Display for TextSpeed {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                TextSpeed::Slow => write!(f, "おそい"),
+                TextSpeed::Normal => write!(f, "ふつう"),
+                TextSpeed::Fast => write!(f, "はやい"),
+                TextSpeed::NoWait => write!(f, "いっしゅん"),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct TextReveal {
+        text: String,
+        shown: f32, // 表示済みの文字数
+        last_tick: Option<Instant>,
+        waited: Duration, // すべて表示してからの経過時間
+    }
+
+    impl TextReveal {
+        // 新しい文章の表示を始める。表示中の文章と同じなら何もしない
+        pub fn show(&mut self, text: String, speed: TextSpeed) {
+            if self.text == text {
+                return;
+            }
+            self.text = text;
+            self.shown = 0.0;
+            self.last_tick = None;
+            self.waited = Duration::ZERO;
+            if speed.chars_per_second().is_none() {
+                self.skip();
+            }
+        }
+
+        // 時刻を進め、経過時間に応じて文字を表示する
+        pub fn tick(&mut self, now: Instant, speed: TextSpeed) {
+            let elapsed = match self.last_tick {
+                Some(last_tick) => now.saturating_duration_since(last_tick),
+                None => Duration::ZERO,
+            };
+            self.last_tick = Some(now);
+            if self.is_finished() {
+                self.waited += elapsed;
+                return;
+            }
+            match speed.chars_per_second() {
+                Some(chars_per_second) => {
+                    self.shown += elapsed.as_secs_f32() * chars_per_second;
+                    self.shown = self.shown.min(self.len() as f32);
+                }
+                None => self.skip(),
+            }
+        }
+
+        // 残りの文字をすべて表示する
+        pub fn skip(&mut self) {
+            self.shown = self.len() as f32;
+        }
+
+        pub fn is_finished(&self) -> bool {
+            self.shown as usize >= self.len()
+        }
+
+        // すべて表示してからの経過時間
+        pub fn waited(&self) -> Duration {
+            self.waited
+        }
+
+        // 表示済みの文字数
+        pub fn shown_chars(&self) -> usize {
+            self.shown as usize
+        }
+
+        // 表示済みの部分の文章
+        pub fn visible(&self) -> String {
+            self.text.chars().take(self.shown_chars()).collect()
+        }
+
+        fn len(&self) -> usize {
+            self.text.chars().count()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_reveal_by_elapsed_time() {
+            let mut reveal = TextReveal::default();
+            reveal.show("てきが　あらわれた！".into(), TextSpeed::Normal);
+            let start = Instant::now();
+            reveal.tick(start, TextSpeed::Normal);
+            assert_eq!(reveal.visible(), "");
+
+            // ふつうの速さでは 0.1 秒で 3 文字
+            reveal.tick(start + Duration::from_millis(100), TextSpeed::Normal);
+            assert_eq!(reveal.visible(), "てきが");
+            assert!(!reveal.is_finished());
+
+            reveal.tick(start + Duration::from_secs(1), TextSpeed::Normal);
+            assert_eq!(reveal.visible(), "てきが　あらわれた！");
+            assert!(reveal.is_finished());
+        }
+
+        #[test]
+        fn test_skip_and_no_wait() {
+            let mut reveal = TextReveal::default();
+            reveal.show("つぎへ".into(), TextSpeed::Slow);
+            reveal.skip();
+            assert!(reveal.is_finished());
+
+            reveal.show("いっしゅん".into(), TextSpeed::NoWait);
+            assert_eq!(reveal.visible(), "いっしゅん");
+        }
+
+        #[test]
+        fn test_same_text_is_not_restarted() {
+            let mut reveal = TextReveal::default();
+            reveal.show("ゆうしゃ".into(), TextSpeed::Normal);
+            reveal.skip();
+            reveal.show("ゆうしゃ".into(), TextSpeed::Normal);
+            assert!(reveal.is_finished());
+        }
+    }
+}
+
+pub use text_reveal::TextReveal;
+pub use text_reveal::TextSpeed;
@@ -0,0 +1,387 @@
+// 敵の強さの自動調整。
+// レベルごとに目標の勝率と戦闘の長さを決め、敵と 1 対 1 で何度も戦闘しながら、
+// hp_max・attack・defence を少しずつ目標に近づける。結果はマスタデータへの差分として提案する。
+#[allow(clippy::module_inception)]
+mod tuning {
+    use crate::battle_runner::{Encounter, Outcome, Party, RunError};
+    use crate::{simulation, MasterData};
+    use std::collections::BTreeMap;
+
+    // 1 回の調整で変える倍率の範囲。大きく変えすぎて目標を飛び越さないようにする
+    const STEP_MIN: f32 = 0.5;
+    const STEP_MAX: f32 = 2.0;
+
+    // 目標に届いたとみなす誤差
+    const WIN_RATE_TOLERANCE: f32 = 0.05;
+    const TURNS_TOLERANCE: f32 = 0.1; // 目標のターン数に対する割合
+
+    // 目標。勝率と、決着までにプレイヤーが行動する平均の回数
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Target {
+        pub win_rate: f32,
+        pub turns: f32,
+    }
+
+    // レベルごとの目標。決めていないレベルは default を使う
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Targets {
+        pub default: Target,
+        pub levels: BTreeMap<u8, Target>,
+    }
+
+    impl Targets {
+        fn of(&self, level: u8) -> Target {
+            self.levels.get(&level).copied().unwrap_or(self.default)
+        }
+
+        // 「レベル:勝率:ターン数」
+        pub fn parse_level(spec: &str) -> Result<(u8, Target), RunError> {
+            let bad_target = || RunError::BadTarget(spec.to_string());
+            let parts: Vec<&str> = spec.split(':').map(str::trim).collect();
+            let [level, win_rate, turns] = parts[..] else {
+                return Err(bad_target());
+            };
+            let level = level.parse().map_err(|_| bad_target())?;
+            let win_rate: f32 = win_rate.parse().map_err(|_| bad_target())?;
+            let turns: f32 = turns.parse().map_err(|_| bad_target())?;
+            if !(0.0..=1.0).contains(&win_rate) || turns <= 0.0 {
+                return Err(bad_target());
+            }
+            Ok((level, Target { win_rate, turns }))
+        }
+    }
+
+    // 調整する能力値
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Stats {
+        pub hp_max: f32,
+        pub attack: f32,
+        pub defence: f32,
+    }
+
+    // 1 体の敵の調整結果
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Tuned {
+        pub name: String,
+        pub level: u8,
+        pub target: Target,
+        pub before: Stats,
+        pub after: Stats,
+        pub win_rate: f32,      // 調整後の勝率
+        pub average_turns: f32, // 調整後の平均ターン数
+        pub iterations: usize,
+    }
+
+    impl Tuned {
+        pub fn changed(&self) -> bool {
+            self.before != self.after
+        }
+    }
+
+    // 敵ごとに、戦闘して目標とのずれを測り、能力値を直すことを繰り返す
+    pub fn tune(
+        master_data: &MasterData,
+        party: &Party,
+        targets: &Targets,
+        battles: usize,
+        iterations: usize,
+        seed: u64,
+    ) -> Result<Vec<Tuned>, RunError> {
+        let mut results = vec![];
+        for idx in 0..master_data.characters.characters.len() {
+            let mut data = master_data.clone();
+            let enemy = &master_data.characters.characters[idx];
+            let target = targets.of(enemy.level.value);
+            let encounter = Encounter::Named(vec![enemy.name.clone()]);
+            let before = Stats {
+                hp_max: enemy.hp_max,
+                attack: enemy.attack,
+                defence: enemy.defence,
+            };
+
+            let mut stats = before;
+            let mut iteration = 0;
+            let (win_rate, average_turns) = loop {
+                let character = &mut data.characters.characters[idx];
+                set_stats(character, stats);
+                let records = simulation::simulate(&data, party, &encounter, battles, seed)?;
+                let count = records.len().max(1) as f32;
+                let victories = records
+                    .iter()
+                    .filter(|record| record.outcome == Outcome::Victory)
+                    .count();
+                let win_rate = victories as f32 / count;
+                let average_turns =
+                    records.iter().map(|record| record.turns).sum::<usize>() as f32 / count;
+                if iteration == iterations || reached(target, win_rate, average_turns) {
+                    break (win_rate, average_turns);
+                }
+                stats = adjust(stats, target, win_rate, average_turns);
+                iteration += 1;
+            };
+
+            results.push(Tuned {
+                name: enemy.name.clone(),
+                level: enemy.level.value,
+                target,
+                before,
+                after: stats,
+                win_rate,
+                average_turns,
+                iterations: iteration,
+            });
+        }
+        Ok(results)
+    }
+
+    fn reached(target: Target, win_rate: f32, average_turns: f32) -> bool {
+        (win_rate - target.win_rate).abs() <= WIN_RATE_TOLERANCE
+            && (average_turns - target.turns).abs() <= target.turns * TURNS_TOLERANCE
+    }
+
+    // 勝ちすぎなら強く、負けすぎなら弱くする。攻撃力は勝率だけで、HP と防御力は勝率とターン数の両方で決める。
+    // 戦闘が短すぎれば HP と防御力を上げ、長すぎれば下げる。ダメージは防御力に反比例するので、両方に半分ずつ振り分ける
+    fn adjust(stats: Stats, target: Target, win_rate: f32, average_turns: f32) -> Stats {
+        let strength = 1.0 + win_rate - target.win_rate;
+        let attack_step = strength.clamp(STEP_MIN, STEP_MAX);
+        let durability_step = (strength * target.turns / average_turns.max(1.0))
+            .clamp(STEP_MIN, STEP_MAX)
+            .sqrt();
+        Stats {
+            hp_max: round(stats.hp_max * durability_step),
+            attack: round(stats.attack * attack_step),
+            defence: round(stats.defence * durability_step),
+        }
+    }
+
+    // マスタデータに書くので、小数第 1 位までにする。0 にはしない
+    fn round(value: f32) -> f32 {
+        ((value * 10.0).round() / 10.0).max(0.1)
+    }
+
+    // 戦闘は hp から始まるので、hp も hp_max と同じ割合で変える
+    fn set_stats(character: &mut crate::battle_rules::Character, stats: Stats) {
+        if character.hp_max > 0.0 {
+            character.hp = round(character.hp * stats.hp_max / character.hp_max);
+        }
+        character.hp_max = stats.hp_max;
+        character.attack = stats.attack;
+        character.defence = stats.defence;
+    }
+
+    // マスタデータの YAML の、調整した敵の能力値の行を書き換える。
+    // ほかの行はそのまま残すので、差分には能力値の行だけが出る
+    pub fn apply_to_yaml(yaml: &str, tuned: &[Tuned]) -> String {
+        let mut lines: Vec<String> = yaml.lines().map(str::to_string).collect();
+        for result in tuned.iter().filter(|result| result.changed()) {
+            let Some(start) = lines
+                .iter()
+                .position(|line| list_item_name(line) == Some(result.name.as_str()))
+            else {
+                continue;
+            };
+            let item_indent = indent_of(&lines[start]);
+            let field_indent = item_indent + 2;
+            let hp_rate = result.after.hp_max / result.before.hp_max;
+            for line in lines.iter_mut().skip(start + 1) {
+                let indent = indent_of(line);
+                if indent <= item_indent && line.trim_start().starts_with("- ") {
+                    break;
+                }
+                if indent != field_indent {
+                    continue;
+                }
+                let Some((key, value)) = line.trim().split_once(':') else {
+                    continue;
+                };
+                let value = match key {
+                    "hp" => value
+                        .trim()
+                        .parse::<f32>()
+                        .ok()
+                        .map(|hp| round(hp * hp_rate)),
+                    "hp_max" => Some(result.after.hp_max),
+                    "attack" => Some(result.after.attack),
+                    "defence" => Some(result.after.defence),
+                    _ => None,
+                };
+                if let Some(value) = value {
+                    *line = format!("{}{}: {:.1}", " ".repeat(field_indent), key, value);
+                }
+            }
+        }
+        let mut text = lines.join("\n");
+        if yaml.ends_with('\n') {
+            text.push('\n');
+        }
+        text
+    }
+
+    fn indent_of(line: &str) -> usize {
+        line.len() - line.trim_start_matches(' ').len()
+    }
+
+    // 「- name: "スライム"」の名前
+    fn list_item_name(line: &str) -> Option<&str> {
+        let name = line.trim().strip_prefix("- name:")?.trim();
+        Some(name.trim_matches('"'))
+    }
+
+    // 行の数が変わらない書き換えの、unified 形式の差分。前後 3 行を添える
+    pub fn unified_diff(path: &str, before: &str, after: &str) -> String {
+        const CONTEXT: usize = 3;
+        let before: Vec<&str> = before.lines().collect();
+        let after: Vec<&str> = after.lines().collect();
+        let changed: Vec<usize> = (0..before.len())
+            .filter(|&idx| before.get(idx) != after.get(idx))
+            .collect();
+        if changed.is_empty() {
+            return String::new();
+        }
+
+        // 近い変更は 1 つのかたまりにまとめる
+        let mut hunks: Vec<(usize, usize)> = vec![];
+        for &idx in &changed {
+            let start = idx.saturating_sub(CONTEXT);
+            let end = (idx + CONTEXT + 1).min(before.len());
+            match hunks.last_mut() {
+                Some(last) if start <= last.1 => last.1 = end,
+                _ => hunks.push((start, end)),
+            }
+        }
+
+        let mut diff = format!("--- a/{}\n+++ b/{}\n", path, path);
+        for (start, end) in hunks {
+            let count = end - start;
+            diff.push_str(&format!(
+                "@@ -{},{} +{},{} @@\n",
+                start + 1,
+                count,
+                start + 1,
+                count
+            ));
+            let mut idx = start;
+            while idx < end {
+                if before[idx] == after[idx] {
+                    diff.push_str(&format!(" {}\n", before[idx]));
+                    idx += 1;
+                    continue;
+                }
+                // 続けて変わった行は、消した行をまとめてから足した行をまとめる
+                let run_end = (idx..end)
+                    .find(|&line| before[line] == after[line])
+                    .unwrap_or(end);
+                for line in &before[idx..run_end] {
+                    diff.push_str(&format!("-{}\n", line));
+                }
+                for line in &after[idx..run_end] {
+                    diff.push_str(&format!("+{}\n", line));
+                }
+                idx = run_end;
+            }
+        }
+        diff
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn tuned(name: &str, before: Stats, after: Stats) -> Tuned {
+            Tuned {
+                name: name.into(),
+                level: 1,
+                target: Target {
+                    win_rate: 0.5,
+                    turns: 3.0,
+                },
+                before,
+                after,
+                win_rate: 0.5,
+                average_turns: 3.0,
+                iterations: 1,
+            }
+        }
+
+        #[test]
+        fn test_adjust_direction() {
+            let stats = Stats {
+                hp_max: 10.0,
+                attack: 10.0,
+                defence: 10.0,
+            };
+            let target = Target {
+                win_rate: 0.5,
+                turns: 8.0,
+            };
+            // 勝ちすぎで短すぎるなら、強くする
+            let stronger = adjust(stats, target, 1.0, 2.0);
+            assert!(stronger.attack > stats.attack);
+            assert!(stronger.hp_max > stats.hp_max);
+            assert!(stronger.defence > stats.defence);
+            // 負けすぎで長すぎるなら、弱くする
+            let weaker = adjust(stats, target, 0.0, 32.0);
+            assert!(weaker.attack < stats.attack);
+            assert!(weaker.hp_max < stats.hp_max);
+        }
+
+        #[test]
+        fn test_yaml_diff() {
+            let yaml = "characters:\n  characters:\n      - name: \"スライム\"\n        hp: 10.0\n        hp_max: 10.0\n        attack: 2.0\n        defence: 1.0\n        skills:\n          skills:\n            - name: \"かみつく\"\n              attack: 2.0\n";
+            let before = Stats {
+                hp_max: 10.0,
+                attack: 2.0,
+                defence: 1.0,
+            };
+            let after = Stats {
+                hp_max: 15.0,
+                attack: 3.0,
+                defence: 1.0,
+            };
+            let tuned_yaml = apply_to_yaml(yaml, &[tuned("スライム", before, after)]);
+            // スキルの行は書き換えない
+            assert!(tuned_yaml.ends_with("              attack: 2.0\n"));
+
+            let diff = unified_diff("example.yml", yaml, &tuned_yaml);
+            let changes: Vec<&str> = diff
+                .lines()
+                .filter(|line| line.starts_with(['-', '+']) && !line.starts_with("---"))
+                .filter(|line| !line.starts_with("+++"))
+                .collect();
+            assert_eq!(
+                changes,
+                vec![
+                    "-        hp: 10.0",
+                    "-        hp_max: 10.0",
+                    "-        attack: 2.0",
+                    "+        hp: 15.0",
+                    "+        hp_max: 15.0",
+                    "+        attack: 3.0",
+                ]
+            );
+            assert!(diff.contains("@@ -1,9 +1,9 @@"));
+        }
+
+        #[test]
+        fn test_parse_target() {
+            assert_eq!(
+                Targets::parse_level("2:0.8:4"),
+                Ok((
+                    2,
+                    Target {
+                        win_rate: 0.8,
+                        turns: 4.0
+                    }
+                ))
+            );
+            assert!(Targets::parse_level("2:80:4").is_err());
+            assert!(Targets::parse_level("2:0.8").is_err());
+        }
+    }
+}
+
+pub use tuning::apply_to_yaml;
+pub use tuning::tune;
+pub use tuning::unified_diff;
+pub use tuning::Target;
+pub use tuning::Targets;
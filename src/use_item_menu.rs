@@ -0,0 +1,122 @@
+// アイテム選択メニュー。
+// 持っているアイテムから、戦闘で使うものを選ぶ。
+#[allow(clippy::module_inception)]
+mod use_item_menu {
+    use crate::command_menu::{self, CommandItem, MenuFrame, MenuKey};
+    use crate::{Item, ItemContainer};
+    #[cfg(feature = "gui")]
+    use iced::widget::Column;
+    #[cfg(feature = "gui")]
+    use iced::Element;
+
+    const TITLE: &str = "どの　アイテムを　つかう？";
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone)]
+    pub enum Message {
+        Initial,
+        OnSelectItem(Item),
+        OnClickNext,
+        OnClickBack,
+    }
+
+    pub struct UseItemMenu {
+        item_containers: Vec<ItemContainer>,
+        pub(crate) item: Option<Item>,
+    }
+
+    impl UseItemMenu {
+        pub(crate) fn new(item_containers: Vec<ItemContainer>) -> Self {
+            UseItemMenu {
+                item_containers,
+                item: None,
+            }
+        }
+
+        pub fn update(&mut self, message: Message) {
+            match message {
+                Message::Initial => {
+                    // 何も選択していない状態にする
+                    self.item = None;
+                }
+                Message::OnSelectItem(item) => {
+                    // 選択しているアイテムを更新する
+                    self.item = Some(item)
+                }
+                Message::OnClickNext => {}
+                Message::OnClickBack => {
+                    // 選択を解除する
+                    self.item = None;
+                }
+            }
+        }
+
+        // 選択しているアイテムの番号
+        fn cursor(&self) -> Option<usize> {
+            let item = self.item.as_ref()?;
+            self.item_containers
+                .iter()
+                .position(|container| &container.item == item)
+        }
+
+        fn items(&self) -> Vec<CommandItem<Message>> {
+            self.item_containers
+                .iter()
+                .map(|container| CommandItem {
+                    label: container.to_string(),
+                    on_select: Some(Message::OnSelectItem(container.item.clone())),
+                })
+                .collect()
+        }
+
+        // キーボードの操作を、このメニューのメッセージに変換する
+        pub fn on_key(&self, key: MenuKey) -> Option<Message> {
+            match key {
+                MenuKey::Up | MenuKey::Down => {
+                    let selectable = vec![true; self.item_containers.len()];
+                    let idx = command_menu::move_cursor(self.cursor(), &selectable, key)?;
+                    Some(Message::OnSelectItem(
+                        self.item_containers[idx].item.clone(),
+                    ))
+                }
+                MenuKey::Confirm => self.item.as_ref().map(|_| Message::OnClickNext),
+                MenuKey::Back => Some(Message::OnClickBack),
+            }
+        }
+
+        #[cfg(feature = "gui")]
+        pub fn view(&self) -> Element<'_, Message> {
+            let mut column = Column::new();
+            if self.item_containers.is_empty() {
+                column = column.push("アイテムを　もっていない。");
+            } else {
+                column = column.push(TITLE);
+                column = column.push(command_menu::view(self.items(), self.cursor()));
+            }
+
+            if self.item.is_some() {
+                // アイテムが選択されている場合、次へ進むためのボタンを表示する
+                let confirm =
+                    iced::widget::button("この　アイテムで　よい").on_press(Message::OnClickNext);
+                column = column.push(confirm);
+            }
+
+            // 戻るボタン
+            column = column.push(iced::widget::button("もどる").on_press(Message::OnClickBack));
+            column.into()
+        }
+
+        // 画面に依存しないメニューの内容
+        pub fn frame(&self) -> MenuFrame {
+            let title = if self.item_containers.is_empty() {
+                "アイテムを　もっていない。"
+            } else {
+                TITLE
+            };
+            command_menu::frame(title, self.items(), self.cursor())
+        }
+    }
+}
+
+pub use use_item_menu::Message;
+pub use use_item_menu::UseItemMenu;
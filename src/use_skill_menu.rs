@@ -1,14 +1,23 @@
+#[allow(clippy::module_inception)]
 mod use_skill_menu {
-    use crate::{Rarity, Skill, Skills};
-    use iced::widget::{pick_list, Column};
+    use crate::battle_rules::Character;
+    use crate::command_menu::{self, CommandItem, MenuFrame, MenuKey};
+    use crate::{Skill, Skills};
+    #[cfg(feature = "gui")]
+    use iced::widget::{text, Column};
+    #[cfg(feature = "gui")]
     use iced::Element;
 
     #[derive(Debug, Clone)]
     pub struct UseSkillMenu {
         skills: Skills,
         pub(crate) skill: Option<Skill>,
+        user: Box<Character>, // スキルの使用者。MP やクールダウンを表示する
     }
 
+    const TITLE: &str = "どの　スキルを　つかう？";
+
+    #[allow(dead_code)]
     #[derive(Debug, Clone)]
     pub enum Message {
         Initial,
@@ -18,10 +27,11 @@ mod use_skill_menu {
     }
 
     impl UseSkillMenu {
-        pub fn new(skills: Skills) -> Self {
+        pub(crate) fn new(skills: Skills, user: Character) -> Self {
             UseSkillMenu {
                 skills,
                 skill: None,
+                user: Box::new(user),
             }
         }
 
@@ -45,14 +55,61 @@ mod use_skill_menu {
             }
         }
 
-        pub fn view(&self) -> Element<Message> {
+        // 選択しているスキルの番号
+        fn cursor(&self) -> Option<usize> {
+            let skill = self.skill.as_ref()?;
+            self.skills
+                .skills
+                .iter()
+                .position(|candidate| candidate == skill)
+        }
+
+        // スキルの一覧。MP が足りないスキルやクールダウン中のスキルは選べない(灰色で表示される)
+        fn items(&self) -> Vec<CommandItem<Message>> {
+            self.skills
+                .skills
+                .iter()
+                .map(|skill| {
+                    let cooldown = self.user.cooldown_of(skill);
+                    let label = if cooldown > 0 {
+                        let mp_cost = skill.mp_cost.value;
+                        format!("{}　MP {}　あと {} ターン", skill.name, mp_cost, cooldown)
+                    } else {
+                        format!("{}　MP {}", skill.name, skill.mp_cost.value)
+                    };
+                    let on_select = self
+                        .user
+                        .can_use(skill)
+                        .then(|| Message::OnSelectSkill(skill.clone()));
+                    CommandItem { label, on_select }
+                })
+                .collect()
+        }
+
+        // キーボードの操作を、このメニューのメッセージに変換する。使えないスキルは飛ばす
+        pub fn on_key(&self, key: MenuKey) -> Option<Message> {
+            match key {
+                MenuKey::Up | MenuKey::Down => {
+                    let selectable: Vec<bool> = self
+                        .skills
+                        .skills
+                        .iter()
+                        .map(|skill| self.user.can_use(skill))
+                        .collect();
+                    let idx = command_menu::move_cursor(self.cursor(), &selectable, key)?;
+                    Some(Message::OnSelectSkill(self.skills.skills[idx].clone()))
+                }
+                MenuKey::Confirm => self.skill.as_ref().map(|_| Message::OnClickNext),
+                MenuKey::Back => Some(Message::OnClickBack),
+            }
+        }
+
+        #[cfg(feature = "gui")]
+        pub fn view(&self) -> Element<'_, Message> {
             let mut column = Column::new();
-            column = column.push("どの　スキルを　つかう？");
-            column = column.push(pick_list(
-                self.skills.skills.clone(),
-                self.skill.clone(),
-                Message::OnSelectSkill,
-            ));
+            column = column.push(TITLE);
+            column = column.push(text!("MP: {} / {}", self.user.mp, self.user.mp_max));
+            column = column.push(command_menu::view(self.items(), self.cursor()));
 
             match &self.skill {
                 Some(_skill) => {
@@ -72,6 +129,11 @@ mod use_skill_menu {
             column = column.push(iced::widget::button("もどる").on_press(Message::OnClickBack));
             column.into()
         }
+
+        // 画面に依存しないメニューの内容
+        pub fn frame(&self) -> MenuFrame {
+            command_menu::frame(TITLE, self.items(), self.cursor())
+        }
     }
 }
 pub use use_skill_menu::UseSkillMenu;
@@ -0,0 +1,128 @@
+// 世界地図。
+// 町・フィールド・ダンジョンの階・魔王の城などのエリアをマスタデータで定義する。
+// エリアに入るとそのエリアのシナリオが進み、立てたフラグで次のエリアへ行けるようになる。
+#[allow(clippy::module_inception)]
+mod world_map {
+    use crate::{battle_operation_menu, Message};
+    use serde::Deserialize;
+    use std::collections::BTreeSet;
+
+    // エリアの集まり。並んだ順にエリア選択の画面に出る
+    #[derive(Debug, Clone, Default, Deserialize)]
+    pub struct WorldMap {
+        pub areas: Vec<Area>,
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct Area {
+        pub name: String,
+        pub kind: AreaKind,
+        #[serde(default)]
+        pub encounter_table: Option<String>, // 使う遭遇表。なければエリアの名前の遭遇表を使う
+        #[serde(default)]
+        pub requires: Vec<String>, // 行けるようになるために、立っていなければならないフラグ
+        #[serde(default)]
+        pub steps: Vec<ScenarioStep>, // エリアに入ったときに進むシナリオ
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+    pub enum AreaKind {
+        Town,
+        Field,
+        Dungeon(u8), // ダンジョンの階
+        Castle,      // 魔王の城
+        Tower,       // ふしぎのとう
+    }
+
+    // エリアのシナリオの 1 こま
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    pub enum ScenarioStep {
+        Info(String),      // 画面上部に情報を表示する
+        Encounter,         // エリアの遭遇表で敵と出会い、戦う
+        SetFlag(String),   // フラグを立てる
+        Explore(String),   // ダンジョンを探索し、ボスを倒したら次に進む
+        Roguelike,         // ふしぎのとうに挑み、結果を見たら次に進む
+        Inn(u32),          // このゴールドで休む。0 ならただの休み場
+        Equipment(String), // そうびを手に入れる。既に持っていれば何もしない
+        Equip,             // そうびを付け外しする
+    }
+
+    impl WorldMap {
+        pub fn find(&self, name: &str) -> Option<&Area> {
+            self.areas.iter().find(|area| area.name == name)
+        }
+    }
+
+    impl Area {
+        // 立っているフラグで、このエリアに行けるか
+        pub fn is_unlocked(&self, flags: &BTreeSet<String>) -> bool {
+            self.requires.iter().all(|flag| flags.contains(flag))
+        }
+
+        // エリアに入ったときの情報
+        pub fn arrival(&self) -> String {
+            match self.kind {
+                AreaKind::Town => format!("{}に　ついた。", self.name),
+                AreaKind::Field => format!("{}に　でた。", self.name),
+                AreaKind::Dungeon(floor) => format!("ちか{}かいへ　おりた。", floor),
+                AreaKind::Castle => format!("{}に　のりこんだ。", self.name),
+                AreaKind::Tower => format!("{}に　いどむ。", self.name),
+            }
+        }
+
+        // 敵と出会うときに使う遭遇表の名前
+        pub fn encounter_table(&self) -> &str {
+            self.encounter_table.as_deref().unwrap_or(&self.name)
+        }
+
+        // エリアのシナリオ。着いたことを知らせてから進め、終わったら世界地図に戻る
+        pub fn scenario(&self) -> Vec<Message> {
+            let mut scenario = vec![Message::Info(self.arrival())];
+            scenario.extend(self.steps.iter().flat_map(|step| match step {
+                ScenarioStep::Info(info) => vec![Message::Info(info.clone())],
+                ScenarioStep::Encounter => vec![
+                    Message::AreaEncounter(self.encounter_table().to_string()),
+                    Message::BattleOperationMenu(battle_operation_menu::Message::Initial),
+                ],
+                ScenarioStep::SetFlag(flag) => vec![Message::SetFlag(flag.clone())],
+                ScenarioStep::Explore(dungeon) => vec![Message::EnterDungeon(dungeon.clone())],
+                ScenarioStep::Roguelike => vec![Message::StartRun],
+                ScenarioStep::Inn(cost) => vec![Message::Rest(*cost)],
+                ScenarioStep::Equipment(name) => vec![Message::GiveEquipment(name.clone())],
+                ScenarioStep::Equip => vec![Message::ShowEquipment],
+            }));
+            scenario.push(Message::ShowWorldMap);
+            scenario
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_area_unlocked_by_flags() {
+            let area = Area {
+                name: "くらやみのどうくつ".into(),
+                kind: AreaKind::Dungeon(1),
+                encounter_table: None,
+                requires: vec!["たびだち".into(), "そうげんを　ぬけた".into()],
+                steps: vec![ScenarioStep::Encounter],
+            };
+            let mut flags = BTreeSet::from(["たびだち".to_string()]);
+            assert!(!area.is_unlocked(&flags));
+            flags.insert("そうげんを　ぬけた".into());
+            assert!(area.is_unlocked(&flags));
+
+            // 着いてから敵と出会って戦い、最後に世界地図へ戻る
+            let scenario = area.scenario();
+            assert!(matches!(&scenario[0], Message::Info(info) if info == "ちか1かいへ　おりた。"));
+            assert!(
+                matches!(&scenario[1], Message::AreaEncounter(table) if table == "くらやみのどうくつ")
+            );
+            assert!(matches!(scenario.last(), Some(Message::ShowWorldMap)));
+        }
+    }
+}
+
+pub use world_map::WorldMap;